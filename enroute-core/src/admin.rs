@@ -0,0 +1,77 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+
+use crate::error::{Error, Result};
+
+
+/// A handle for managing the topics of a message broker, created by
+/// [`Broker::admin`](crate::broker::Broker::admin).
+///
+/// Every operation defaults to failing with [`Error::Unsupported`], so backends only
+/// implement the operations they support.
+#[async_trait]
+pub trait Admin: Send + Sync {
+    /// Create a topic with the backend's default settings.
+    ///
+    /// # Arguments
+    /// * `topic` - The name of the topic to create.
+    ///
+    /// # Returns
+    /// A result indicating success or failure.
+    async fn create_topic(&self, _topic: &str) -> Result<()> {
+        Err(Error::Unsupported("creating topics".to_string()))
+    }
+    /// Delete a topic.
+    ///
+    /// # Arguments
+    /// * `topic` - The name of the topic to delete.
+    ///
+    /// # Returns
+    /// A result indicating success or failure.
+    async fn delete_topic(&self, _topic: &str) -> Result<()> {
+        Err(Error::Unsupported("deleting topics".to_string()))
+    }
+    /// List the topics of the broker.
+    ///
+    /// # Returns
+    /// A result containing the topic names or an error.
+    async fn list_topics(&self) -> Result<Vec<String>> {
+        Err(Error::Unsupported("listing topics".to_string()))
+    }
+}
+
+/// An admin supporting no operation, for brokers without topic management.
+#[derive(Debug, Clone)]
+pub struct UnsupportedAdmin;
+
+#[async_trait]
+impl Admin for UnsupportedAdmin {}
+
+/// A type-erased admin that can hold any concrete admin implementation.
+#[derive(Clone)]
+pub struct AnyAdmin(Arc<dyn Admin>);
+
+impl AnyAdmin {
+    pub fn new<A: Admin + 'static>(admin: A) -> Self {
+        Self(Arc::new(admin))
+    }
+
+    pub fn into_inner(self) -> Arc<dyn Admin> {
+        self.0
+    }
+}
+
+#[async_trait]
+impl Admin for AnyAdmin {
+    async fn create_topic(&self, topic: &str) -> Result<()> {
+        self.0.create_topic(topic).await
+    }
+
+    async fn delete_topic(&self, topic: &str) -> Result<()> {
+        self.0.delete_topic(topic).await
+    }
+
+    async fn list_topics(&self) -> Result<Vec<String>> {
+        self.0.list_topics().await
+    }
+}
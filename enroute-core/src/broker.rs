@@ -2,7 +2,8 @@ use std::sync::Arc;
 use async_trait::async_trait;
 
 use crate::{
-    error::Result,
+    error::{Error, Result},
+    admin::{Admin, AnyAdmin},
     publisher::{Publisher, AnyPublisher, IntoAnyPublisher, PublisherOptions},
     consumer::{Consumer, AnyConsumer, IntoAnyConsumer, ConsumerOptions},
 };
@@ -13,6 +14,7 @@ use crate::{
 pub trait Broker: Send + Sync {
     type Publisher: Publisher;
     type Consumer: Consumer;
+    type Admin: Admin;
 
     /// Create a publisher with the given options.
     /// 
@@ -43,6 +45,22 @@ pub trait Broker: Send + Sync {
             self.consumer(options.1).await?,
         ))
     }
+    /// List the channels known to the broker.
+    ///
+    /// # Returns
+    /// A result containing the channel names, or an [`Error::Unsupported`] if the
+    /// broker cannot list its channels.
+    async fn channels(&self) -> Result<Vec<String>> {
+        Err(Error::Unsupported("listing channels".to_string()))
+    }
+    /// Create a handle for managing the broker's topics.
+    ///
+    /// # Returns
+    /// A result containing the admin handle, or an [`Error::Unsupported`] if the broker
+    /// doesn't support topic management.
+    async fn admin(&self) -> Result<Self::Admin> {
+        Err(Error::Unsupported("topic management".to_string()))
+    }
 }
 
 /// A builder for creating brokers.
@@ -54,7 +72,7 @@ pub trait BrokerBuilder: Send + Sync {
 }
 
 /// A type-erased broker that can hold any concrete broker implementation.
-pub struct AnyBroker(Arc<dyn Broker<Publisher = AnyPublisher, Consumer = AnyConsumer>>);
+pub struct AnyBroker(Arc<dyn Broker<Publisher = AnyPublisher, Consumer = AnyConsumer, Admin = AnyAdmin>>);
 
 impl AnyBroker {
     pub fn new<B>(broker: B) -> Self
@@ -62,11 +80,12 @@ impl AnyBroker {
         B: Broker + 'static,
         B::Publisher: Publisher + IntoAnyPublisher,
         B::Consumer: Consumer + IntoAnyConsumer,
+        B::Admin: 'static,
     {
         Self(Arc::new(BrokerAdapter { inner: broker }))
     }
 
-    pub fn into_inner(self) -> Arc<dyn Broker<Publisher = AnyPublisher, Consumer = AnyConsumer>> {
+    pub fn into_inner(self) -> Arc<dyn Broker<Publisher = AnyPublisher, Consumer = AnyConsumer, Admin = AnyAdmin>> {
         self.0
     }
 }
@@ -75,6 +94,7 @@ impl AnyBroker {
 impl Broker for AnyBroker {
     type Publisher = AnyPublisher;
     type Consumer = AnyConsumer;
+    type Admin = AnyAdmin;
 
     async fn publisher(&self, options: PublisherOptions) -> Result<Self::Publisher> {
         self.0.publisher(options).await
@@ -83,6 +103,14 @@ impl Broker for AnyBroker {
     async fn consumer(&self, options: ConsumerOptions) -> Result<Self::Consumer> {
         self.0.consumer(options).await
     }
+
+    async fn channels(&self) -> Result<Vec<String>> {
+        self.0.channels().await
+    }
+
+    async fn admin(&self) -> Result<Self::Admin> {
+        self.0.admin().await
+    }
 }
 
 struct BrokerAdapter<B: Broker> {
@@ -95,9 +123,11 @@ where
     B: Broker + Send + Sync,
     B::Publisher: Publisher + IntoAnyPublisher,
     B::Consumer: Consumer + IntoAnyConsumer,
+    B::Admin: 'static,
 {
     type Publisher = AnyPublisher;
     type Consumer = AnyConsumer;
+    type Admin = AnyAdmin;
 
     async fn publisher(&self, options: PublisherOptions) -> Result<Self::Publisher> {
         Ok(self.inner.publisher(options).await?.into_any())
@@ -106,6 +136,14 @@ where
     async fn consumer(&self, options: ConsumerOptions) -> Result<Self::Consumer> {
         Ok(self.inner.consumer(options).await?.into_any())
     }
+
+    async fn channels(&self) -> Result<Vec<String>> {
+        self.inner.channels().await
+    }
+
+    async fn admin(&self) -> Result<Self::Admin> {
+        Ok(AnyAdmin::new(self.inner.admin().await?))
+    }
 }
 
 /// A trait for converting a broker into a type-erased [`AnyBroker`].
@@ -118,6 +156,7 @@ where
     B: Broker + 'static,
     B::Publisher: Publisher + IntoAnyPublisher,
     B::Consumer: Consumer + IntoAnyConsumer,
+    B::Admin: 'static,
 {
     fn into_any(self) -> AnyBroker {
         AnyBroker::new(self)
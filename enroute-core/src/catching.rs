@@ -0,0 +1,188 @@
+use std::{
+    any::Any,
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::{Pin, pin},
+    sync::{Arc, atomic::{AtomicU64, Ordering}},
+};
+use async_trait::async_trait;
+use futures::{Stream, StreamExt, FutureExt, future::{self, Either}};
+
+use crate::{
+    error::Result,
+    envelope::Envelope,
+    event::Event,
+    consumer::{Consumer, ConsumerEvent},
+    publisher::Publisher,
+};
+
+
+type PanicHook = Arc<dyn Fn(&Event, &str) + Send + Sync>;
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("handler panicked")
+}
+
+/// A consumer whose [`CatchingConsumer::run_until_shutdown`] survives handler panics.
+///
+/// A panic while handling an event is caught, the event is settled as a failure, and
+/// the loop moves on to the next event, so a single bad message can't take down the
+/// whole consumer. Caught panics are logged as errors with the [`log`] crate. By
+/// default the envelope of the offending event is nacked; with a dead-letter publisher
+/// the event is published there and the envelope acked instead.
+/// Streams are passed through from the inner consumer untouched.
+pub struct CatchingConsumer<C: Consumer> {
+    inner: C,
+    dead_letter: Option<Arc<dyn Publisher>>,
+    on_panic: Option<PanicHook>,
+    panics: Arc<AtomicU64>,
+}
+
+impl<C: Consumer> CatchingConsumer<C> {
+    /// Create a new [`CatchingConsumer`] wrapping the given consumer.
+    ///
+    /// # Arguments
+    /// * `inner` - The consumer to run handlers for.
+    ///
+    /// # Returns
+    /// The new [`CatchingConsumer`].
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            dead_letter: None,
+            on_panic: None,
+            panics: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Publish the events whose handler panicked to a dead-letter channel.
+    ///
+    /// The envelope is acked once the event has been published, and nacked if publishing
+    /// fails.
+    ///
+    /// # Arguments
+    /// * `publisher` - The publisher of the dead-letter channel.
+    ///
+    /// # Returns
+    /// The consumer with the dead-letter publisher set.
+    pub fn with_dead_letter(mut self, publisher: Arc<dyn Publisher>) -> Self {
+        self.dead_letter = Some(publisher);
+        self
+    }
+
+    /// Set a function called with the event and the panic message of every caught
+    /// panic, in addition to logging it, e.g. to report it.
+    ///
+    /// # Arguments
+    /// * `hook` - The function to call.
+    ///
+    /// # Returns
+    /// The consumer with the hook set.
+    pub fn with_on_panic<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Event, &str) + Send + Sync + 'static,
+    {
+        self.on_panic = Some(Arc::new(hook));
+        self
+    }
+
+    /// Returns the number of handler panics caught so far.
+    pub fn panics(&self) -> u64 {
+        self.panics.load(Ordering::Relaxed)
+    }
+
+    /// Returns a reference to the inner consumer.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Process incoming events with a handler until the shutdown future completes, as
+    /// [`Consumer::run_until_shutdown`] does, catching handler panics.
+    ///
+    /// This is an inherent method rather than an override of the trait method, so it's
+    /// only used when calling it on the [`CatchingConsumer`] itself, not through a
+    /// type-erased consumer such as an [`AnyConsumer`](crate::consumer::AnyConsumer).
+    ///
+    /// # Arguments
+    /// * `shutdown` - The future signalling shutdown, e.g. on SIGTERM.
+    /// * `handler` - The function handling each event.
+    ///
+    /// # Returns
+    /// A result indicating a graceful shutdown, or an error if the stream couldn't be
+    /// started or the consumer couldn't be closed.
+    pub async fn run_until_shutdown<S, F, Fut>(&self, shutdown: S, mut handler: F) -> Result<()>
+    where
+        S: Future<Output = ()> + Send,
+        F: FnMut(Event) -> Fut + Send,
+        Fut: Future<Output = Result<()>> + Send,
+    {
+        let mut envelopes = self.stream_events().await?;
+        let mut shutdown = pin!(shutdown);
+
+        loop {
+            let envelope = match future::select(envelopes.next(), shutdown.as_mut()).await {
+                Either::Left((Some(Ok(envelope)), _)) => envelope,
+                Either::Left((Some(Err(_)), _)) => continue,
+                Either::Left((None, _)) | Either::Right(_) => break,
+            };
+
+            let event = envelope.event().clone();
+            let handled = AssertUnwindSafe(async { handler(event).await })
+                .catch_unwind()
+                .await;
+
+            match handled {
+                Ok(Ok(())) => envelope.ack().await,
+                Ok(Err(_)) => envelope.nack().await,
+                Err(payload) => self.settle_panicked(envelope, panic_message(payload.as_ref())).await,
+            }
+        }
+
+        drop(envelopes);
+        self.close().await
+    }
+
+    async fn settle_panicked(&self, envelope: Envelope, message: &str) {
+        self.panics.fetch_add(1, Ordering::Relaxed);
+        log::error!("handler panicked on event {}: {}", envelope.event().id(), message);
+
+        if let Some(hook) = &self.on_panic {
+            hook(envelope.event(), message);
+        }
+
+        match &self.dead_letter {
+            Some(publisher) => match publisher.publish_event(envelope.event().clone()).await {
+                Ok(()) => envelope.ack().await,
+                Err(_) => envelope.nack().await,
+            },
+            None => envelope.nack().await,
+        }
+    }
+}
+
+#[async_trait]
+impl<C: Consumer> Consumer for CatchingConsumer<C> {
+    async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
+        self.inner.stream_events().await
+    }
+
+    async fn pause(&self) -> Result<()> {
+        self.inner.pause().await
+    }
+
+    async fn resume(&self) -> Result<()> {
+        self.inner.resume().await
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    async fn stream_with_control(&self) -> Result<Pin<Box<dyn Stream<Item = Result<ConsumerEvent>> + Send>>> {
+        self.inner.stream_with_control().await
+    }
+}
@@ -0,0 +1,59 @@
+use std::{sync::{Arc, Mutex}, fmt::Debug};
+use chrono::{DateTime, TimeDelta, Utc};
+
+
+/// A source of the current time, injected where events are stamped so that tests can
+/// control it.
+pub trait Clock: Send + Sync + Debug {
+    /// Returns the current time.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// A clock reading the system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock returning a fixed time, which only changes when set or advanced.
+///
+/// Clones share the same time, so a test can keep one clone to move the time of the
+/// components it injected the others into.
+#[derive(Debug, Clone)]
+pub struct FixedClock {
+    time: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl FixedClock {
+    pub fn new(time: DateTime<Utc>) -> Self {
+        Self { time: Arc::new(Mutex::new(time)) }
+    }
+
+    /// Set the time returned by the clock.
+    pub fn set(&self, time: DateTime<Utc>) {
+        *self.time
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = time;
+    }
+
+    /// Move the time returned by the clock forward by the given delta.
+    pub fn advance(&self, delta: TimeDelta) {
+        let mut time = self.time
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        *time += delta;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.time
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+}
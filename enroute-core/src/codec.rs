@@ -0,0 +1,65 @@
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::error::Result;
+#[cfg(feature = "cbor")]
+use crate::error::Error;
+
+
+/// The content type of CBOR encoded data.
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+/// Trait for binary encodings of event data, used with [`EventBuilder::build_encoded`].
+///
+/// [`EventBuilder::build_encoded`]: crate::event::EventBuilder::build_encoded
+pub trait Codec {
+    /// The content type of the encoded data.
+    const CONTENT_TYPE: &'static str;
+
+    /// Encode a value.
+    ///
+    /// # Arguments
+    /// * `value` - The value to encode.
+    ///
+    /// # Returns
+    /// A result containing the encoded bytes or an error.
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>>;
+
+    /// Decode a value.
+    ///
+    /// # Arguments
+    /// * `bytes` - The encoded bytes.
+    ///
+    /// # Returns
+    /// A result containing the decoded value or an error.
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T>;
+}
+
+/// A codec encoding event data as CBOR (RFC 8949), with the `application/cbor` content
+/// type.
+///
+/// Events built with it store their data as binary, which [`Event::data`] decodes by its
+/// content type, so it suits constrained devices such as MQTT clients.
+///
+/// [`Event::data`]: crate::event::Event::data
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CborCodec;
+
+#[cfg(feature = "cbor")]
+impl Codec for CborCodec {
+    const CONTENT_TYPE: &'static str = CBOR_CONTENT_TYPE;
+
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+
+        ciborium::into_writer(value, &mut bytes)
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+
+        Ok(bytes)
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+        ciborium::from_reader(bytes)
+            .map_err(|e| Error::Deserialization(e.to_string()))
+    }
+}
@@ -1,11 +1,53 @@
-use std::{sync::Arc, pin::Pin};
+use std::{any::Any, sync::Arc, pin::{Pin, pin}, collections::HashMap, future::Future};
 use async_trait::async_trait;
-use futures::Stream;
+use futures::{Stream, StreamExt, future::{self, Either}};
 use serde::{Serialize, Deserialize};
+#[cfg(feature = "tokio")]
+use futures::stream;
+#[cfg(feature = "tokio")]
+use tokio_util::sync::CancellationToken;
 
-use crate::{error::Result, envelope::Envelope};
+use crate::{
+    error::{Error, Result},
+    envelope::Envelope,
+    event::{Event, EventData},
+    typed::{TypedStream, FailureStream, split_typed},
+};
 
 
+/// The delivery guarantee a consumer provides for the events it yields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryGuarantee {
+    /// Each event is delivered at most once. Events are settled as soon as they are
+    /// yielded, so an event that fails processing or is nacked is lost.
+    AtMostOnce,
+    /// Each event is delivered at least once. Events are only settled once they are
+    /// acked, so an event that is nacked or never acked may be delivered again.
+    AtLeastOnce,
+}
+
+/// A partition of a channel, assigned to or revoked from a consumer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Partition {
+    /// The channel the partition belongs to.
+    pub channel: String,
+    /// The index of the partition.
+    pub partition: i32,
+}
+
+/// An item of the stream returned by [`Consumer::stream_with_control`], either an
+/// incoming message or a change of the consumer's assignment.
+#[derive(Debug, Clone)]
+pub enum ConsumerEvent {
+    /// An incoming message.
+    Message(Envelope),
+    /// Partitions were assigned to the consumer by a rebalance.
+    Assigned(Vec<Partition>),
+    /// Partitions were revoked from the consumer by a rebalance.
+    Revoked(Vec<Partition>),
+}
+
 /// Options for configuring a consumer.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConsumerOptions {
@@ -13,6 +55,25 @@ pub struct ConsumerOptions {
     pub channel: String,
     /// The consumer tag to identify the consumer.
     pub consumer_tag: String,
+    /// Backend-specific settings, merged into the backend's client configuration.
+    #[serde(default)]
+    pub extra: HashMap<String, String>,
+    /// Overrides the broker's default of whether nacked messages are requeued,
+    /// for backends that support requeueing. Takes precedence over the delivery guarantee.
+    #[serde(default)]
+    pub requeue_override: Option<bool>,
+    /// The delivery guarantee of the consumer. When not set, the backend's default
+    /// behavior applies.
+    #[serde(default)]
+    pub delivery_guarantee: Option<DeliveryGuarantee>,
+    /// The maximum number of envelopes yielded but not yet acked or nacked. When
+    /// reached, the stream waits for an envelope to be settled before yielding the next.
+    #[serde(default)]
+    pub max_in_flight: Option<usize>,
+    /// Whether the consumer first receives the events published on its channel before
+    /// it was created, for backends retaining them, then the live ones.
+    #[serde(default)]
+    pub replay_from_start: bool,
 }
 
 impl ConsumerOptions {
@@ -27,6 +88,11 @@ impl ConsumerOptions {
 pub struct ConsumerOptionsBuilder {
     channel: Option<String>,
     consumer_tag: Option<String>,
+    extra: HashMap<String, String>,
+    requeue_override: Option<bool>,
+    delivery_guarantee: Option<DeliveryGuarantee>,
+    max_in_flight: Option<usize>,
+    replay_from_start: bool,
 }
 
 impl ConsumerOptionsBuilder {
@@ -60,18 +126,110 @@ impl ConsumerOptionsBuilder {
         self
     }
 
+    /// Set a backend-specific setting.
+    /// 
+    /// # Arguments
+    /// * `key` - The name of the setting, as understood by the backend.
+    /// * `value` - The value of the setting.
+    /// 
+    /// # Returns
+    /// The builder with the setting added.
+    pub fn extra(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.extra.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set multiple backend-specific settings.
+    /// 
+    /// # Arguments
+    /// * `extra` - The settings to add, as understood by the backend.
+    /// 
+    /// # Returns
+    /// The builder with the settings added.
+    pub fn extras(mut self, extra: HashMap<impl Into<String>, impl Into<String>>) -> Self {
+        self.extra.extend(extra.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Override the broker's default of whether nacked messages are requeued.
+    /// 
+    /// # Arguments
+    /// * `requeue` - Whether nacked messages should be requeued for this consumer.
+    /// 
+    /// # Returns
+    /// The builder with the requeue override set.
+    pub fn requeue_override(mut self, requeue: bool) -> Self {
+        self.requeue_override = Some(requeue);
+        self
+    }
+
+    /// Set the delivery guarantee of the consumer.
+    /// 
+    /// # Arguments
+    /// * `guarantee` - The delivery guarantee the consumer should provide.
+    /// 
+    /// # Returns
+    /// The builder with the delivery guarantee set.
+    pub fn delivery_guarantee(mut self, guarantee: DeliveryGuarantee) -> Self {
+        self.delivery_guarantee = Some(guarantee);
+        self
+    }
+
+    /// Set the maximum number of envelopes yielded but not yet acked or nacked.
+    /// 
+    /// # Arguments
+    /// * `max` - The maximum number of unsettled envelopes.
+    /// 
+    /// # Returns
+    /// The builder with the in-flight limit set.
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = Some(max);
+        self
+    }
+
+    /// Set whether the consumer first receives the events published on its channel
+    /// before it was created, for backends retaining them.
+    /// 
+    /// # Arguments
+    /// * `replay` - Whether past events are replayed to the consumer.
+    /// 
+    /// # Returns
+    /// The builder with the replay setting set.
+    pub fn replay_from_start(mut self, replay: bool) -> Self {
+        self.replay_from_start = replay;
+        self
+    }
+
     /// Build the [`ConsumerOptions`] from the builder.
     /// 
     /// # Returns
-    /// The build [`ConsumerOptions`].
+    /// The built [`ConsumerOptions`].
     /// 
     /// # Panics
-    /// If the channel or consumer tag is not set.
+    /// If the channel or consumer tag is not set. Use [`ConsumerOptionsBuilder::try_build`]
+    /// when the options come from dynamic configuration.
     pub fn build(self) -> ConsumerOptions {
-        ConsumerOptions {
-            channel: self.channel.expect("channel is required"),
-            consumer_tag: self.consumer_tag.expect("consumer_tag is required"),
-        }
+        self.try_build()
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Try to build the [`ConsumerOptions`] from the builder.
+    /// 
+    /// # Returns
+    /// A result containing the built [`ConsumerOptions`], or an [`Error::Builder`]
+    /// if the channel or consumer tag is not set.
+    pub fn try_build(self) -> Result<ConsumerOptions> {
+        Ok(ConsumerOptions {
+            channel: self.channel
+                .ok_or_else(|| Error::Builder("channel is required".to_string()))?,
+            consumer_tag: self.consumer_tag
+                .ok_or_else(|| Error::Builder("consumer_tag is required".to_string()))?,
+            extra: self.extra,
+            requeue_override: self.requeue_override,
+            delivery_guarantee: self.delivery_guarantee,
+            max_in_flight: self.max_in_flight,
+            replay_from_start: self.replay_from_start,
+        })
     }
 }
 
@@ -84,28 +242,192 @@ pub trait Consumer: Send + Sync {
     /// # Returns
     /// A result containing a stream of message envelopes or an error.
     async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>>;
+    /// Pause consumption, so streams stop yielding envelopes until the consumer is
+    /// resumed. The consumer keeps its subscription while paused.
+    /// 
+    /// The default implementation does nothing, for consumers that can't pause.
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    async fn pause(&self) -> Result<()> {
+        Ok(())
+    }
+    /// Resume consumption after a [`Consumer::pause`].
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    async fn resume(&self) -> Result<()> {
+        Ok(())
+    }
+    /// Close the consumer once it is done consuming, releasing its resources, e.g.
+    /// committing the offsets of acked events.
+    /// 
+    /// The default implementation does nothing, for consumers without resources to release.
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+    /// Stream incoming events until the given token is cancelled.
+    /// 
+    /// Once the token is cancelled no new events are pulled, the underlying event stream
+    /// is dropped, and the consumer is closed with [`Consumer::close`] before the stream
+    /// ends, yielding the error of closing it, if any. Envelopes already yielded have to
+    /// be settled before polling the stream again, so that closing the consumer accounts
+    /// for them, e.g. when committing offsets.
+    /// 
+    /// # Arguments
+    /// * `token` - The token cancelling the stream.
+    /// 
+    /// # Returns
+    /// A result containing a stream of message envelopes or an error.
+    #[cfg(feature = "tokio")]
+    async fn stream_events_cancellable(&self, token: CancellationToken) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send + '_>>> {
+        let events = Box::pin(
+            self.stream_events()
+                .await?
+                .take_until(token.cancelled_owned())
+        );
+
+        Ok(Box::pin(stream::unfold(Some(events), move |events| async move {
+            let mut events = events?;
+
+            match events.next().await {
+                Some(envelope) => Some((envelope, Some(events))),
+                None => {
+                    drop(events);
+                    self.close().await.err().map(|e| (Err(e), None))
+                }
+            }
+        })))
+    }
+    /// Stream incoming events interleaved with changes of the consumer's assignment, so
+    /// a single loop can react to both.
+    /// 
+    /// The default implementation only yields [`ConsumerEvent::Message`], for backends
+    /// without rebalances.
+    /// 
+    /// # Returns
+    /// A result containing a stream of consumer events or an error.
+    async fn stream_with_control(&self) -> Result<Pin<Box<dyn Stream<Item = Result<ConsumerEvent>> + Send>>> {
+        Ok(Box::pin(
+            self.stream_events()
+                .await?
+                .map(|envelope| envelope.map(ConsumerEvent::Message))
+        ))
+    }
+    /// Stream incoming events deserialized into the given type, routing the envelopes
+    /// that fail to deserialize to a separate stream (see [`split_typed`]).
+    /// 
+    /// # Returns
+    /// A result containing the typed stream and the failure stream, or an error.
+    async fn stream_typed_with_errors<E: EventData>(&self) -> Result<(TypedStream<E>, FailureStream)>
+    where
+        Self: Sized,
+    {
+        Ok(split_typed(self.stream_events().await?))
+    }
+    /// Process incoming events with a handler until the shutdown future completes.
+    /// 
+    /// Events are handled one at a time, and each envelope is acked when the handler
+    /// succeeds and nacked when it fails. Errors of the event stream are skipped. Once
+    /// the shutdown future completes or the stream ends no new events are pulled, the
+    /// event being handled, if any, is settled, and the consumer is closed with
+    /// [`Consumer::close`] before this returns.
+    /// 
+    /// # Arguments
+    /// * `shutdown` - The future signalling shutdown, e.g. on SIGTERM.
+    /// * `handler` - The function handling each event.
+    /// 
+    /// # Returns
+    /// A result indicating a graceful shutdown, or an error if the stream couldn't be
+    /// started or the consumer couldn't be closed.
+    async fn run_until_shutdown<S, F, Fut>(&self, shutdown: S, mut handler: F) -> Result<()>
+    where
+        Self: Sized,
+        S: Future<Output = ()> + Send,
+        F: FnMut(Event) -> Fut + Send,
+        Fut: Future<Output = Result<()>> + Send,
+    {
+        let mut envelopes = self.stream_events().await?;
+        let mut shutdown = pin!(shutdown);
+
+        loop {
+            let envelope = match future::select(envelopes.next(), shutdown.as_mut()).await {
+                Either::Left((Some(Ok(envelope)), _)) => envelope,
+                Either::Left((Some(Err(_)), _)) => continue,
+                Either::Left((None, _)) | Either::Right(_) => break,
+            };
+
+            match handler(envelope.event().clone()).await {
+                Ok(()) => envelope.ack().await,
+                Err(_) => envelope.nack().await,
+            }
+        }
+
+        drop(envelopes);
+        self.close().await
+    }
 }
 
 /// A type-erased consumer that can hold any concrete consumer implementation.
-pub struct AnyConsumer(Arc<dyn Consumer>);
+pub struct AnyConsumer {
+    inner: Arc<dyn Consumer>,
+    any: Arc<dyn Any + Send + Sync>,
+}
 
 impl AnyConsumer {
     pub fn new<C>(consumer: C) -> Self
     where
         C: Consumer + 'static,
     {
-        Self(Arc::new(consumer))
+        let consumer = Arc::new(consumer);
+
+        Self {
+            inner: consumer.clone(),
+            any: consumer,
+        }
     }
 
     pub fn into_inner(self) -> Arc<dyn Consumer> {
-        self.0
+        self.inner
+    }
+
+    /// Returns a reference to the concrete consumer if it is of type `C`.
+    /// 
+    /// # Returns
+    /// A reference to the concrete consumer, or `None` if it is of a different type.
+    pub fn downcast_ref<C: Consumer + 'static>(&self) -> Option<&C> {
+        self.any.downcast_ref::<C>()
+    }
+
+    /// Returns whether the concrete consumer is of type `C`.
+    pub fn is<C: Consumer + 'static>(&self) -> bool {
+        self.any.is::<C>()
     }
 }
 
 #[async_trait]
 impl Consumer for AnyConsumer {
     async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
-        self.0.stream_events().await
+        self.inner.stream_events().await
+    }
+
+    async fn pause(&self) -> Result<()> {
+        self.inner.pause().await
+    }
+
+    async fn resume(&self) -> Result<()> {
+        self.inner.resume().await
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    async fn stream_with_control(&self) -> Result<Pin<Box<dyn Stream<Item = Result<ConsumerEvent>> + Send>>> {
+        self.inner.stream_with_control().await
     }
 }
 
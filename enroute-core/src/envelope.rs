@@ -1,9 +1,44 @@
-use std::{sync::Arc, fmt::Debug};
+use std::{sync::Arc, fmt::Debug, time::Duration};
 use async_trait::async_trait;
+use uuid::Uuid;
 
-use crate::event::Event;
+use crate::{
+    error::{Error, Result},
+    event::{Event, ExtensionValue},
+};
 
 
+/// The extension counting how many times an event has been delivered, for backends
+/// that track redeliveries.
+/// 
+/// Its name follows the CloudEvents naming rules, lowercase letters and digits, so it is
+/// carried unchanged by binary-mode bindings that prefix extensions with `ce-`.
+pub const DELIVERY_COUNT_EXTENSION: &str = "deliverycount";
+
+/// Returns how many times the event has been delivered, as recorded by its
+/// [`DELIVERY_COUNT_EXTENSION`], or `1` if it doesn't carry it.
+pub fn delivery_count(event: &Event) -> u32 {
+    match event.0.extension(DELIVERY_COUNT_EXTENSION) {
+        Some(ExtensionValue::Integer(count)) => u32::try_from(*count).ok(),
+        Some(ExtensionValue::String(count)) => count.parse().ok(),
+        _ => None,
+    }
+    .unwrap_or(1)
+}
+
+/// The extension marking an event as a tombstone, i.e. a record with a key and no
+/// value, which on compacted topics signals that the key was deleted.
+pub const TOMBSTONE_EXTENSION: &str = "tombstone";
+
+/// Returns whether the event is a tombstone, as marked by its [`TOMBSTONE_EXTENSION`].
+pub fn is_tombstone(event: &Event) -> bool {
+    match event.0.extension(TOMBSTONE_EXTENSION) {
+        Some(ExtensionValue::Boolean(tombstone)) => *tombstone,
+        Some(ExtensionValue::String(tombstone)) => tombstone == "true",
+        _ => false,
+    }
+}
+
 /// An acker that can acknowledge or negatively acknowledge message processing.
 #[async_trait]
 pub trait Acker: Send + Sync + Debug {
@@ -11,6 +46,19 @@ pub trait Acker: Send + Sync + Debug {
     async fn ack(&self);
     /// Negatively acknowledge failed message processing.
     async fn nack(&self);
+    /// Extend the deadline by which the message must be settled before the backend
+    /// redelivers it, for handlers that run long.
+    /// 
+    /// Backends without settlement deadlines do nothing.
+    /// 
+    /// # Arguments
+    /// * `by` - How long to extend the deadline by, from now.
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    async fn extend_deadline(&self, _by: Duration) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// A no-operation acker that does nothing on ack or nack.
@@ -29,11 +77,12 @@ impl Acker for NoOpAcker {
 pub struct Envelope {
     event: Event,
     acker: Arc<dyn Acker>,
+    recv_id: Uuid,
 }
 
 impl Envelope {
     pub fn new(event: Event, acker: Arc<dyn Acker>) -> Self {
-        Self { event, acker }
+        Self { event, acker, recv_id: Uuid::new_v4() }
     }
 
     /// Create a noop envelope with a no-operation acker.
@@ -44,7 +93,7 @@ impl Envelope {
     /// # Returns
     /// A noop envelope containing the event.
     pub fn noop(event: Event) -> Self {
-        Self { event, acker: Arc::new(NoOpAcker) }
+        Self::new(event, Arc::new(NoOpAcker))
     }
 
     /// Get a reference to the event contained in the envelope.
@@ -55,6 +104,78 @@ impl Envelope {
         &self.event
     }
 
+    /// Returns the id generated when the envelope was received, unique to this delivery.
+    /// 
+    /// Unlike the event id, which is set by the publisher and may repeat, e.g. when the
+    /// event is redelivered, the receive id identifies a single delivery attempt, to
+    /// correlate its processing in logs and traces. Clones of the envelope share it.
+    pub fn recv_id(&self) -> Uuid {
+        self.recv_id
+    }
+
+    /// Returns how many times the event has been delivered, including this delivery.
+    /// 
+    /// The count is read from the [`DELIVERY_COUNT_EXTENSION`], and is `1` for events
+    /// that don't carry it, either because this is their first delivery or because the
+    /// backend doesn't track redeliveries.
+    pub fn delivery_count(&self) -> u32 {
+        delivery_count(&self.event)
+    }
+
+    /// Returns whether the event is a tombstone, for backends that surface deletions.
+    /// 
+    /// A tombstone carries the key that was deleted as its subject and has no data, as
+    /// opposed to an event with an empty payload.
+    pub fn is_tombstone(&self) -> bool {
+        is_tombstone(&self.event)
+    }
+
+    /// Serialize the envelope's event into the CloudEvents JSON format, to hand it to
+    /// another process.
+    /// 
+    /// Only the event crosses the boundary: the acker stays with this envelope, so the
+    /// event must still be settled here, as acking or nacking the envelope rebuilt with
+    /// [`Envelope::from_transport_bytes`] has no effect.
+    /// 
+    /// # Returns
+    /// A result containing the serialized event or an error.
+    pub fn to_transport_bytes(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec(&self.event)
+            .map_err(|e| Error::Serialization(e.to_string()))
+    }
+
+    /// Rebuild an envelope from bytes produced by [`Envelope::to_transport_bytes`], with
+    /// a [`NoOpAcker`].
+    /// 
+    /// # Arguments
+    /// * `bytes` - The serialized event.
+    /// 
+    /// # Returns
+    /// A result containing the envelope or an error.
+    pub fn from_transport_bytes(bytes: &[u8]) -> Result<Self> {
+        serde_json::from_slice(bytes)
+            .map(Self::noop)
+            .map_err(|e| Error::Deserialization(e.to_string()))
+    }
+
+    /// Replace the acker of the envelope with one wrapping it, keeping the receive id, for
+    /// consumers that observe or extend how envelopes are settled.
+    /// 
+    /// # Arguments
+    /// * `f` - The function wrapping the acker.
+    /// 
+    /// # Returns
+    /// The envelope with the new acker.
+    pub fn map_acker(mut self, f: impl FnOnce(Arc<dyn Acker>) -> Arc<dyn Acker>) -> Self {
+        self.acker = f(self.acker);
+        self
+    }
+
+    /// Consume the envelope and return its event and acker.
+    pub fn into_parts(self) -> (Event, Arc<dyn Acker>) {
+        (self.event, self.acker)
+    }
+
     /// Acknowledge successful processing of the event.
     pub async fn ack(&self) {
         self.acker.ack().await;
@@ -64,4 +185,84 @@ impl Envelope {
     pub async fn nack(&self) {
         self.acker.nack().await;
     }
-}
\ No newline at end of file
+
+    /// Extend the deadline by which the event must be settled before it is redelivered
+    /// (see [`Acker::extend_deadline`]).
+    /// 
+    /// # Arguments
+    /// * `by` - How long to extend the deadline by, from now.
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn extend_deadline(&self, by: Duration) -> Result<()> {
+        self.acker.extend_deadline(by).await
+    }
+}
+
+
+/// A group of ackers settled together, for checkpoint-style acknowledgement.
+/// 
+/// Collect the ackers of the envelopes processed since the last checkpoint, then ack
+/// them all once the checkpoint is committed, or nack them all if it failed.
+#[derive(Debug, Clone, Default)]
+pub struct AckGroup {
+    ackers: Vec<Arc<dyn Acker>>,
+}
+
+impl AckGroup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an acker to the group.
+    pub fn push(&mut self, acker: Arc<dyn Acker>) {
+        self.ackers.push(acker);
+    }
+
+    /// Add the acker of an envelope to the group.
+    pub fn push_envelope(&mut self, envelope: &Envelope) {
+        self.ackers.push(envelope.acker.clone());
+    }
+
+    /// Returns the number of ackers in the group.
+    pub fn len(&self) -> usize {
+        self.ackers.len()
+    }
+
+    /// Returns whether the group holds no ackers.
+    pub fn is_empty(&self) -> bool {
+        self.ackers.is_empty()
+    }
+
+    /// Remove every acker from the group, to start collecting towards the next checkpoint.
+    pub fn clear(&mut self) {
+        self.ackers.clear();
+    }
+
+    /// Acknowledge successful processing of every acker in the group, in insertion order.
+    pub async fn ack_all(&self) {
+        for acker in &self.ackers {
+            acker.ack().await;
+        }
+    }
+
+    /// Negatively acknowledge failed processing of every acker in the group, in insertion
+    /// order.
+    pub async fn nack_all(&self) {
+        for acker in &self.ackers {
+            acker.nack().await;
+        }
+    }
+}
+
+impl FromIterator<Arc<dyn Acker>> for AckGroup {
+    fn from_iter<I: IntoIterator<Item = Arc<dyn Acker>>>(iter: I) -> Self {
+        Self { ackers: iter.into_iter().collect() }
+    }
+}
+
+impl<'a> FromIterator<&'a Envelope> for AckGroup {
+    fn from_iter<I: IntoIterator<Item = &'a Envelope>>(iter: I) -> Self {
+        Self { ackers: iter.into_iter().map(|envelope| envelope.acker.clone()).collect() }
+    }
+}
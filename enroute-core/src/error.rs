@@ -2,6 +2,7 @@ use thiserror::{Error as ThisError};
 
 
 #[derive(ThisError, Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// An error occurred during serialization of the event.
     #[error("Serialization error: {0}")]
@@ -9,6 +10,13 @@ pub enum Error {
     /// An error occurred during deserialization of the event.
     #[error("Deserialization error: {0}")]
     Deserialization(String),
+    /// A JSON error occurred where the direction (serialization or deserialization)
+    /// is not known.
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    /// An I/O error occurred.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
     /// Missing event data in the envelope.
     #[error("Missing event data")]
     MissingEventData,
@@ -21,9 +29,58 @@ pub enum Error {
     /// An error occurred in the broker builder.
     #[error("Builder error: {0}")]
     Builder(String),
+    /// An operation did not complete in time.
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    /// The operation is not supported by the backend.
+    #[error("Unsupported operation: {0}")]
+    Unsupported(String),
     /// An unknown error occurred.
     #[error("Unknown error: {0}")]
     Unknown(#[from] anyhow::Error),
 }
 
+impl Error {
+    /// Returns the kind of the error, a stable discriminant to match on when converting
+    /// it into application errors.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Serialization(_) => ErrorKind::Serialization,
+            Error::Deserialization(_) => ErrorKind::Deserialization,
+            Error::Json(_) => ErrorKind::Json,
+            Error::Io(_) => ErrorKind::Io,
+            Error::MissingEventData => ErrorKind::MissingEventData,
+            Error::Publisher(_) => ErrorKind::Publisher,
+            Error::Consumer(_) => ErrorKind::Consumer,
+            Error::Builder(_) => ErrorKind::Builder,
+            Error::Timeout(_) => ErrorKind::Timeout,
+            Error::Unsupported(_) => ErrorKind::Unsupported,
+            Error::Unknown(_) => ErrorKind::Unknown,
+        }
+    }
+}
+
+/// The kind of an [`Error`], as returned by [`Error::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    Serialization,
+    Deserialization,
+    Json,
+    Io,
+    MissingEventData,
+    Publisher,
+    Consumer,
+    Builder,
+    Timeout,
+    Unsupported,
+    Unknown,
+}
+
+// Errors must stay usable across tasks and in boxed application errors.
+const _: fn() = || {
+    fn assert_error<T: std::error::Error + Send + Sync + 'static>() {}
+    assert_error::<Error>();
+};
+
 pub type Result<T> = std::result::Result<T, Error>;
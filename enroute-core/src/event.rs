@@ -1,18 +1,64 @@
-use std::collections::HashMap;
-use serde::{Serialize, Deserialize};
-use serde_json::{to_value, to_vec, to_string, from_value, from_slice, from_str, Value};
+use std::{collections::HashMap, sync::Arc};
+use serde::{Serialize, Deserialize, Deserializer, de::Error as _};
+use serde_json::{to_value, to_vec, to_vec_pretty, to_string, from_value, from_slice, from_str, Map, Value};
 use anyhow::anyhow;
+use base64::{Engine, prelude::BASE64_STANDARD};
 use cloudevents::{
     AttributesReader,
     Data as CloudEventData,
     Event as CloudEvent,
     EventBuilder as CloudEventBuilder,
     EventBuilderV10 as CloudEventBuilderV10,
-    event::{TryIntoTime, TryIntoUrl, ExtensionValue},
+    event::{TryIntoTime, TryIntoUrl},
 };
 use url::Url;
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
 
-use crate::error::{Error, Result};
+pub use cloudevents::event::ExtensionValue;
+
+use crate::{
+    error::{Error, Result},
+    clock::Clock,
+    codec::Codec,
+};
+#[cfg(feature = "cbor")]
+use crate::codec::{CborCodec, CBOR_CONTENT_TYPE};
+
+
+/// The names of the CloudEvents context attributes, which extensions may not use.
+pub const RESERVED_ATTRIBUTE_NAMES: &[&str] = &[
+    "specversion",
+    "id",
+    "source",
+    "type",
+    "datacontenttype",
+    "dataschema",
+    "subject",
+    "time",
+    "data",
+    "data_base64",
+];
+
+/// The extension carrying an application-level idempotency key, written by publishers
+/// as a header or attribute so that consumers and external dedup layers can discard
+/// duplicates across producer sessions.
+pub const IDEMPOTENCY_KEY_EXTENSION: &str = "idempotencykey";
+
+/// The extension carrying the id shared by every event of a causal chain, set on
+/// events created with [`Event::derive_child`].
+pub const CORRELATION_ID_EXTENSION: &str = "correlationid";
+
+/// The extension carrying the id of the event that caused an event, set on events
+/// created with [`Event::derive_child`].
+pub const CAUSATION_ID_EXTENSION: &str = "causationid";
+
+/// The extension carrying the RFC 3339 time after which an event is expired and should
+/// be dropped instead of processed (see [`Event::is_expired`]).
+pub const EXPIRY_TIME_EXTENSION: &str = "expirytime";
+
+/// The content type of the CloudEvents JSON batch format.
+pub const BATCH_CONTENT_TYPE: &str = "application/cloudevents-batch+json";
 
 
 /// Trait for event data types.
@@ -21,6 +67,25 @@ pub trait EventData: Serialize + for<'de> Deserialize<'de> + Send + Sync + Clone
     fn event_type() -> &'static str;
     /// Returns the channel name as a static string.
     fn channel_name() -> &'static str;
+    /// Returns the subject of events carrying this data, set by [`EventBuilder::build`]
+    /// when no subject was set on the builder. Derived from the `subject_field` argument
+    /// of `#[event_data]`, if given.
+    fn subject(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Resolves the schema URL of event types, to populate the `dataschema` attribute of
+/// events built with [`EventBuilder::schema_resolver`].
+pub trait SchemaResolver: Send + Sync {
+    /// Returns the schema URL of the given event type, if it has one.
+    fn resolve(&self, event_type: &str) -> Option<String>;
+}
+
+impl SchemaResolver for HashMap<String, String> {
+    fn resolve(&self, event_type: &str) -> Option<String> {
+        self.get(event_type).cloned()
+    }
 }
 
 /// An empty event data type.
@@ -33,10 +98,177 @@ impl EventData for EmptyEventData {
 }
 
 
+/// Encodes an extension value as a string, for transports that only carry strings,
+/// such as Kafka headers.
+///
+/// Strings are kept as is, integers are written in decimal and booleans as `true`
+/// or `false`. See [`parse_extension_value`] for the inverse.
+pub fn extension_value_to_string(value: &ExtensionValue) -> String {
+    value.to_string()
+}
+
+/// Parses an extension value encoded by [`extension_value_to_string`], inferring its type.
+///
+/// `true` and `false` are parsed as booleans and decimal numbers fitting an `i64` as
+/// integers, anything else is kept as a string. Since strings are not tagged, a string
+/// extension that looks like a boolean or an integer is parsed as one.
+pub fn parse_extension_value(value: &str) -> ExtensionValue {
+    match value {
+        "true" => ExtensionValue::Boolean(true),
+        "false" => ExtensionValue::Boolean(false),
+        _ => value
+            .parse::<i64>()
+            .map(ExtensionValue::Integer)
+            .unwrap_or_else(|_| ExtensionValue::String(value.to_string())),
+    }
+}
+
+
+/// The form in which an event's data is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataKind {
+    /// The data is stored as raw bytes.
+    Binary,
+    /// The data is stored as a parsed JSON value.
+    Json,
+    /// The data is stored as a string.
+    String,
+}
+
+/// Options controlling how [`EventBuilder::build`] and [`EventBuilder::build_value`]
+/// serialize the event data. The default stores the data as is, serialized compactly.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerdeOptions {
+    /// Store the data as pretty-printed JSON bytes, so it is published pretty-printed.
+    pub pretty: bool,
+    /// Convert the keys of every JSON object in the data to snake_case, regardless of
+    /// the serde attributes of the data type.
+    pub snake_case_keys: bool,
+}
+
+fn to_snake_case(key: &str) -> String {
+    let chars = key.chars().collect::<Vec<_>>();
+    let mut snake = String::with_capacity(key.len() + 4);
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '-' || c == ' ' || c == '_' {
+            if !snake.ends_with('_') {
+                snake.push('_');
+            }
+            continue;
+        }
+
+        if c.is_uppercase() {
+            let prev = i.checked_sub(1).map(|j| chars[j]);
+            let next = chars.get(i + 1);
+            let boundary = prev.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit())
+                || (prev.is_some_and(char::is_uppercase) && next.is_some_and(|n| n.is_lowercase()));
+
+            if boundary && !snake.ends_with('_') {
+                snake.push('_');
+            }
+
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+
+    snake
+}
+
+fn snake_case_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| (to_snake_case(&k), snake_case_keys(v)))
+                .collect::<Map<_, _>>()
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(snake_case_keys).collect()),
+        value => value,
+    }
+}
+
+fn redact_fields(value: Value, fields: &[&str]) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(k, v)| match fields.contains(&k.as_str()) {
+                    true => (k, Value::String(REDACTED_VALUE.to_string())),
+                    false => (k, redact_fields(v, fields)),
+                })
+                .collect::<Map<_, _>>()
+        ),
+        Value::Array(values) => Value::Array(
+            values.into_iter()
+                .map(|v| redact_fields(v, fields))
+                .collect()
+        ),
+        value => value,
+    }
+}
+
+
+/// Computes the JSON merge patch (RFC 7396) turning `old` into `new`.
+fn merge_patch(old: &Value, new: &Value) -> Value {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let removed = old
+                .keys()
+                .filter(|k| !new.contains_key(*k))
+                .map(|k| (k.clone(), Value::Null));
+            let changed = new
+                .iter()
+                .filter(|(k, v)| old.get(*k) != Some(*v))
+                .map(|(k, v)| match old.get(k) {
+                    Some(old) => (k.clone(), merge_patch(old, v)),
+                    None => (k.clone(), v.clone()),
+                });
+
+            Value::Object(removed.chain(changed).collect())
+        }
+        (_, new) => new.clone(),
+    }
+}
+
+/// The content type of events built by [`EventBuilder::build_diff`].
+pub const MERGE_PATCH_CONTENT_TYPE: &str = "application/merge-patch+json";
+
+/// The value replacing the fields of [`Event::redacted`] events.
+pub const REDACTED_VALUE: &str = "***";
+
 /// A type for containing an event's information.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct Event(pub CloudEvent);
 
+impl<'de> Deserialize<'de> for Event {
+    /// Deserialize an event from the CloudEvents JSON format.
+    /// 
+    /// Unlike [`CloudEvent`], which parses `data_base64` into a JSON value when the content
+    /// type is JSON, the decoded bytes are kept as binary data, so [`Event::data_as_bytes`]
+    /// returns the original payload verbatim.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let mut value = Value::deserialize(deserializer)?;
+        let data_base64 = match value.get("data_base64") {
+            Some(Value::String(_)) => value
+                .as_object_mut()
+                .and_then(|map| map.remove("data_base64")),
+            _ => None,
+        };
+        let mut event = from_value::<CloudEvent>(value).map_err(D::Error::custom)?;
+
+        if let Some(Value::String(encoded)) = data_base64 {
+            event.set_data_unchecked(
+                BASE64_STANDARD
+                    .decode(encoded)
+                    .map_err(D::Error::custom)?
+            );
+        }
+
+        Ok(Self(event))
+    }
+}
+
 impl Event {
     pub fn new(event: CloudEvent) -> Self {
         Self(event)
@@ -92,6 +324,73 @@ impl Event {
         self.0.subject()
     }
 
+    /// Returns the idempotency key of the event, if it carries an
+    /// [`IDEMPOTENCY_KEY_EXTENSION`].
+    pub fn idempotency_key(&self) -> Option<String> {
+        self.0
+            .extension(IDEMPOTENCY_KEY_EXTENSION)
+            .map(extension_value_to_string)
+    }
+
+    /// Returns the correlation id of the event, if it carries a
+    /// [`CORRELATION_ID_EXTENSION`].
+    pub fn correlation_id(&self) -> Option<String> {
+        self.0
+            .extension(CORRELATION_ID_EXTENSION)
+            .map(extension_value_to_string)
+    }
+
+    /// Returns the causation id of the event, if it carries a [`CAUSATION_ID_EXTENSION`].
+    pub fn causation_id(&self) -> Option<String> {
+        self.0
+            .extension(CAUSATION_ID_EXTENSION)
+            .map(extension_value_to_string)
+    }
+
+    /// Returns the time after which the event is expired, if it carries a valid
+    /// [`EXPIRY_TIME_EXTENSION`].
+    pub fn expiry_time(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.0
+            .extension(EXPIRY_TIME_EXTENSION)
+            .map(extension_value_to_string)
+            .and_then(|time| chrono::DateTime::parse_from_rfc3339(&time).ok())
+            .map(|time| time.with_timezone(&chrono::Utc))
+    }
+
+    /// Returns whether the event's [`EXPIRY_TIME_EXTENSION`] is past. Events without a
+    /// valid expiry time never expire.
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(chrono::Utc::now())
+    }
+
+    /// Returns whether the event is expired at the given time, e.g. the time of a
+    /// [`Clock`].
+    /// 
+    /// # Arguments
+    /// * `now` - The time to compare the expiry time against.
+    pub fn is_expired_at(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.expiry_time()
+            .is_some_and(|expiry| expiry <= now)
+    }
+
+    /// Create a builder for an event published in response to this one, linked to it for
+    /// lineage tracking.
+    /// 
+    /// The builder is seeded with the correlation id of this event, or with its id if it
+    /// starts a chain, and with its id as the causation id. All other attributes are
+    /// left to be set.
+    /// 
+    /// # Returns
+    /// The seeded builder.
+    pub fn derive_child(&self) -> EventBuilder {
+        EventBuilder::new()
+            .extension(
+                CORRELATION_ID_EXTENSION,
+                self.correlation_id().unwrap_or_else(|| self.id().to_string()),
+            )
+            .extension(CAUSATION_ID_EXTENSION, self.id())
+    }
+
     /// Returns a map of all extensions.
     pub fn extensions(&self) -> HashMap<String, ExtensionValue> {
         self.0.iter_extensions()
@@ -99,7 +398,52 @@ impl Event {
             .collect()
     }
 
+    /// Sets an extension on the event in place, replacing any value it already has.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the extension.
+    /// * `value` - The value of the extension.
+    pub fn set_extension(&mut self, name: &str, value: impl Into<ExtensionValue>) {
+        self.0.set_extension(name, value);
+    }
+
+    /// Removes an extension from the event in place, if it carries it.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the extension.
+    pub fn remove_extension(&mut self, name: &str) {
+        self.0.remove_extension(name);
+    }
+
+    /// Sets the given default extensions on the event, keeping any extension
+    /// the event already carries with the same name.
+    ///
+    /// # Arguments
+    /// * `defaults` - The default extensions to apply.
+    pub fn apply_default_extensions(&mut self, defaults: &HashMap<String, ExtensionValue>) {
+        for (name, value) in defaults {
+            if self.0.extension(name).is_none() {
+                self.0.set_extension(name, value.clone());
+            }
+        }
+    }
+
+    /// Returns the form in which the event data is stored, or `None` if the
+    /// event carries no data.
+    pub fn data_kind(&self) -> Option<DataKind> {
+        self.0
+            .data()
+            .map(|data| match data {
+                CloudEventData::Binary(_) => DataKind::Binary,
+                CloudEventData::Json(_) => DataKind::Json,
+                CloudEventData::String(_) => DataKind::String,
+            })
+    }
+
     /// Returns the event data as serialized bytes.
+    /// 
+    /// Binary data, as set by [`EventBuilder::build_raw`] or consumed from a binary
+    /// payload, is returned verbatim, while JSON data is serialized anew.
     pub fn data_as_bytes(&self) -> Result<Vec<u8>> {
         match self.0
             .data()
@@ -112,6 +456,25 @@ impl Event {
         }
     }
 
+    /// Returns the event data as shared bytes, consuming the event.
+    /// 
+    /// Binary data is moved into the [`Bytes`] without a copy, so relays can forward the
+    /// payload and clone it cheaply. JSON data is serialized anew.
+    #[cfg(feature = "bytes")]
+    pub fn into_data_bytes(mut self) -> Result<Bytes> {
+        match self.0
+            .take_data()
+            .2
+            .ok_or(Error::MissingEventData)?
+        {
+            CloudEventData::Binary(bytes) => Ok(Bytes::from(bytes)),
+            CloudEventData::Json(value) => to_vec(&value)
+                .map(Bytes::from)
+                .map_err(|e| Error::Serialization(e.to_string())),
+            CloudEventData::String(s) => Ok(Bytes::from(s)),
+        }
+    }
+
     /// Returns the event data as a [`serde_json::Value`].
     pub fn data_as_value(&self) -> Result<Value> {
         match self.0
@@ -119,14 +482,19 @@ impl Event {
             .ok_or(Error::MissingEventData)?
         {
             CloudEventData::Json(value) => Ok(value.clone()),
-            CloudEventData::Binary(bytes) => from_slice(&bytes)
+            #[cfg(feature = "cbor")]
+            CloudEventData::Binary(bytes) if self.is_cbor() => CborCodec::decode(bytes),
+            CloudEventData::Binary(bytes) => from_slice(bytes)
                 .map_err(|e| Error::Deserialization(e.to_string())),
-            CloudEventData::String(s) => from_str(&s)
+            CloudEventData::String(s) => from_str(s)
                 .map_err(|e| Error::Deserialization(e.to_string())),
         }
     }
 
     /// Returns the event data as a JSON string.
+    /// 
+    /// Binary data is decoded as by [`Event::data_as_value`], so binary data in another
+    /// format, e.g. protobuf, yields an [`Error::Deserialization`] naming its content type.
     pub fn data_as_string(&self) -> Result<String> {
         match self.0
             .data()
@@ -135,13 +503,23 @@ impl Event {
             CloudEventData::String(s) => Ok(s.clone()),
             CloudEventData::Json(value) => serde_json::to_string(&value)
                 .map_err(|e| Error::Serialization(e.to_string())),
-            CloudEventData::Binary(bytes) => from_slice::<Value>(&bytes)
-                .and_then(|v| to_string(&v))
-                .map_err(|e| Error::Serialization(e.to_string())),
+            CloudEventData::Binary(_) => {
+                let value = self.data_as_value()
+                    .map_err(|_| Error::Deserialization(format!(
+                        "binary data of content type {} can't be read as JSON",
+                        self.datacontenttype().unwrap_or("application/octet-stream"),
+                    )))?;
+
+                to_string(&value)
+                    .map_err(|e| Error::Serialization(e.to_string()))
+            }
         }
     }
 
     /// Returns the event data deserialized into the specified type.
+    /// 
+    /// Binary data is decoded by its content type, as CBOR for `application/cbor` when the
+    /// `cbor` feature is enabled and as JSON otherwise.
     pub fn data<E: EventData>(&self) -> Result<E> {
         match self.0
             .data()
@@ -149,13 +527,118 @@ impl Event {
         {
             CloudEventData::Json(value) => from_value(value.clone())
                 .map_err(|e| Error::Deserialization(e.to_string())),
-            CloudEventData::Binary(bytes) => from_slice(&bytes)
+            #[cfg(feature = "cbor")]
+            CloudEventData::Binary(bytes) if self.is_cbor() => CborCodec::decode(bytes),
+            CloudEventData::Binary(bytes) => from_slice(bytes)
                 .map_err(|e| Error::Deserialization(e.to_string())),
-            CloudEventData::String(s) => from_str(&s)
+            CloudEventData::String(s) => from_str(s)
                 .map_err(|e| Error::Deserialization(e.to_string())),
         }
     }
 
+    #[cfg(feature = "cbor")]
+    fn is_cbor(&self) -> bool {
+        self.0
+            .datacontenttype()
+            .is_some_and(|content_type| content_type == CBOR_CONTENT_TYPE)
+    }
+
+    /// Returns whether this event matches another, ignoring the given attributes.
+    ///
+    /// Attribute and extension names are compared as they appear in the CloudEvents
+    /// spec (e.g. `id`, `time`, `source`). The special name `data` skips comparing
+    /// the event data.
+    ///
+    /// # Arguments
+    /// * `other` - The event to compare against.
+    /// * `ignore` - The names of the attributes to skip.
+    ///
+    /// # Returns
+    /// `true` if all non-ignored attributes and the data are equal.
+    pub fn matches_ignoring(&self, other: &Event, ignore: &[&str]) -> bool {
+        let lhs = self.0
+            .iter()
+            .filter(|(name, _)| !ignore.contains(name))
+            .collect::<HashMap<_, _>>();
+        let rhs = other.0
+            .iter()
+            .filter(|(name, _)| !ignore.contains(name))
+            .collect::<HashMap<_, _>>();
+
+        if lhs != rhs {
+            return false;
+        }
+
+        ignore.contains(&"data") || self.0.data() == other.0.data()
+    }
+
+    /// Serializes the whole event, attributes and extensions as well as data, in the
+    /// structured CloudEvents JSON format.
+    pub fn to_json(&self) -> Result<String> {
+        to_string(self)
+            .map_err(|e| Error::Serialization(e.to_string()))
+    }
+
+    /// Serializes the whole event to bytes in the structured CloudEvents JSON format
+    /// (see [`Event::to_json`]).
+    pub fn to_json_bytes(&self) -> Result<Vec<u8>> {
+        to_vec(self)
+            .map_err(|e| Error::Serialization(e.to_string()))
+    }
+
+    /// Returns a copy of the event with the named data fields replaced by
+    /// [`REDACTED_VALUE`], to log or trace events without leaking sensitive data.
+    /// 
+    /// Fields are redacted wherever they appear in the data, including nested objects and
+    /// arrays. Data that isn't JSON is left as is.
+    /// 
+    /// # Arguments
+    /// * `fields` - The names of the fields to redact.
+    /// 
+    /// # Returns
+    /// The redacted copy of the event.
+    pub fn redacted(&self, fields: &[&str]) -> Event {
+        let mut event = self.clone();
+
+        if let Ok(value) = self.data_as_value() {
+            let value = redact_fields(value, fields);
+
+            // CBOR data is decoded to be redacted, so it's encoded back to keep matching
+            // its content type.
+            #[cfg(feature = "cbor")]
+            if self.is_cbor() {
+                if let Ok(bytes) = CborCodec::encode(&value) {
+                    event.0.set_data(CBOR_CONTENT_TYPE, bytes);
+                }
+
+                return event;
+            }
+
+            let content_type = self
+                .datacontenttype()
+                .unwrap_or("application/json")
+                .to_string();
+
+            event.0.set_data(content_type, value);
+        }
+
+        event
+    }
+
+    /// Serializes the events into the CloudEvents JSON batch format
+    /// (see [`BATCH_CONTENT_TYPE`]).
+    pub fn to_batch_json(events: &[Event]) -> Result<Vec<u8>> {
+        to_vec(events)
+            .map_err(|e| Error::Serialization(e.to_string()))
+    }
+
+    /// Deserializes events from the CloudEvents JSON batch format
+    /// (see [`BATCH_CONTENT_TYPE`]).
+    pub fn from_batch_json(bytes: &[u8]) -> Result<Vec<Event>> {
+        from_slice(bytes)
+            .map_err(|e| Error::Deserialization(e.to_string()))
+    }
+
     /// Returns an empty event.
     pub fn empty() -> Self {
         EventBuilder::new()
@@ -167,10 +650,40 @@ impl Event {
 }
 
 
+fn validate_strict(event: &Event) -> Result<()> {
+    if event.subject().is_none_or(str::is_empty) {
+        return Err(Error::Builder("subject is required".to_string()));
+    }
+
+    let source = event.source();
+
+    if source.is_empty()
+        || source.chars().any(|c| c.is_whitespace() || c.is_control())
+        || Url::parse("http://localhost/").and_then(|base| base.join(source)).is_err()
+    {
+        return Err(Error::Builder(format!("source is not a valid URI-reference: {}", source)));
+    }
+
+    if let Some((name, _)) = event.0
+        .iter_extensions()
+        .find(|(name, _)| RESERVED_ATTRIBUTE_NAMES.contains(name))
+    {
+        return Err(Error::Builder(format!("extension name is reserved: {}", name)));
+    }
+
+    Ok(())
+}
+
+
 pub struct EventBuilder {
     inner: CloudEventBuilderV10,
     schema_url: Option<String>,
+    schema_resolver: Option<Arc<dyn SchemaResolver>>,
     error: Option<Error>,
+    strict: bool,
+    serde_options: SerdeOptions,
+    has_subject: bool,
+    data: Option<(Option<String>, CloudEventData)>,
 }
 
 impl EventBuilder {
@@ -178,10 +691,53 @@ impl EventBuilder {
         Self {
             inner: CloudEventBuilderV10::default(),
             schema_url: None,
+            schema_resolver: None,
+            error: None,
+            strict: false,
+            serde_options: SerdeOptions::default(),
+            has_subject: false,
+            data: None,
+        }
+    }
+
+    /// Create a builder pre-populated with the attributes, extensions and data of an
+    /// existing event, to re-emit it with a few changes.
+    /// 
+    /// Finish with [`EventBuilder::rebuild`] to keep the event's data, or with
+    /// [`EventBuilder::build`] or [`EventBuilder::build_raw`] to replace it.
+    /// 
+    /// # Arguments
+    /// * `event` - The event to start from.
+    /// 
+    /// # Returns
+    /// The pre-populated builder.
+    pub fn from_event(event: &Event) -> Self {
+        Self {
+            inner: CloudEventBuilderV10::from(event.0.clone()),
+            schema_url: event.dataschema().map(Url::to_string),
+            schema_resolver: None,
             error: None,
+            strict: false,
+            serde_options: SerdeOptions::default(),
+            has_subject: event.subject().is_some(),
+            data: event.0
+                .data()
+                .cloned()
+                .map(|data| (event.datacontenttype().map(str::to_string), data)),
         }
     }
 
+    /// Enable strict validation of the built event.
+    ///
+    /// On top of the attributes required by CloudEvents, a strict builder requires a
+    /// non-empty subject, a source that is a valid URI-reference, and extension names
+    /// that don't clash with [`RESERVED_ATTRIBUTE_NAMES`]. Violations fail the build
+    /// with an [`Error::Builder`].
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
     pub fn id(mut self, id: impl Into<String>) -> Self {
         self.inner = self.inner.id(id);
         self
@@ -210,17 +766,26 @@ impl EventBuilder {
 
     pub fn subject(mut self, subject: impl Into<String>) -> Self {
         self.inner = self.inner.subject(subject);
+        self.has_subject = true;
         self
     }
 
     pub fn maybe_subject(mut self, subject: Option<impl Into<String>>) -> Self {
         if let Some(subject) = subject {
             self.inner = self.inner.subject(subject);
+            self.has_subject = true;
         }
 
         self
     }
 
+    /// Stamp the event with the current time of the given clock, in place of the system
+    /// time set by default.
+    pub fn clock(mut self, clock: &dyn Clock) -> Self {
+        self.inner = self.inner.time(clock.now());
+        self
+    }
+
     pub fn time(mut self, time: impl TryIntoTime) -> Self {
         self.inner = self.inner.time(time);
         self
@@ -247,6 +812,21 @@ impl EventBuilder {
         self
     }
 
+    /// Set the time after which the event is expired, as its [`EXPIRY_TIME_EXTENSION`].
+    pub fn expiry(mut self, time: chrono::DateTime<chrono::Utc>) -> Self {
+        self.inner = self.inner.extension(
+            EXPIRY_TIME_EXTENSION,
+            time.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+        );
+        self
+    }
+
+    /// Set the idempotency key of the event, as its [`IDEMPOTENCY_KEY_EXTENSION`].
+    pub fn idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.inner = self.inner.extension(IDEMPOTENCY_KEY_EXTENSION, key.into());
+        self
+    }
+
     pub fn extensions(mut self, extensions: HashMap<impl AsRef<str>, impl Into<ExtensionValue>>) -> Self {
         for (k, v) in extensions {
             self.inner = self.inner.extension(k.as_ref(), v);
@@ -290,6 +870,21 @@ impl EventBuilder {
         self
     }
 
+    /// Set how the event data is serialized by [`EventBuilder::build`] and
+    /// [`EventBuilder::build_value`].
+    pub fn serde_options(mut self, options: SerdeOptions) -> Self {
+        self.serde_options = options;
+        self
+    }
+
+    /// Set a resolver populating the `dataschema` attribute from the event type when the
+    /// event is built with [`EventBuilder::build`]. A schema URL set explicitly takes
+    /// precedence over the resolved one.
+    pub fn schema_resolver(mut self, resolver: Arc<dyn SchemaResolver>) -> Self {
+        self.schema_resolver = Some(resolver);
+        self
+    }
+
     pub fn type_<T: AsRef<str>>(mut self, type_: T) -> Self {
         self.inner = self.inner.ty(type_.as_ref());
         self
@@ -304,6 +899,10 @@ impl EventBuilder {
     }
 
     pub fn build<E: EventData>(mut self, data: E) -> Result<Event> {
+        if !self.has_subject {
+            self = self.maybe_subject(data.subject());
+        }
+
         let value = match to_value(&data) {
             Ok(v) => v,
             Err(e) => {
@@ -311,22 +910,148 @@ impl EventBuilder {
             }
         };
 
+        self.build_value(E::event_type(), value)
+    }
+
+    /// Build the event with its data encoded by the given codec, stored as binary data
+    /// under the codec's content type.
+    /// 
+    /// # Arguments
+    /// * `data` - The data of the event.
+    /// 
+    /// # Returns
+    /// A result containing the event or an error.
+    pub fn build_encoded<C: Codec, E: EventData>(mut self, data: E) -> Result<Event> {
+        if !self.has_subject {
+            self = self.maybe_subject(data.subject());
+        }
+
+        let bytes = C::encode(&data)?;
+
+        self.build_raw_with_content_type(E::event_type(), bytes, C::CONTENT_TYPE)
+    }
+
+    /// Build a change event whose data is the JSON merge patch (RFC 7396) turning the old
+    /// value into the new one, with the `application/merge-patch+json` content type.
+    /// 
+    /// Fields removed from the new value are set to `null` in the patch, so fields whose
+    /// new value is `null` can't be told apart from removed ones.
+    /// 
+    /// # Arguments
+    /// * `old` - The value before the change.
+    /// * `new` - The value after the change, which the event type and subject are taken
+    ///   from.
+    /// 
+    /// # Returns
+    /// A result containing the event or an error.
+    pub fn build_diff<E: EventData>(mut self, old: &E, new: &E) -> Result<Event> {
+        if !self.has_subject {
+            self = self.maybe_subject(new.subject());
+        }
+
+        if self.schema_url.is_none() {
+            let resolved = self.schema_resolver
+                .as_ref()
+                .and_then(|resolver| resolver.resolve(E::event_type()));
+
+            self = self.maybe_schema_url(resolved);
+        }
+
         if let Some(err) = self.error.take() {
             return Err(err);
         }
 
+        let old = to_value(old)
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        let new = to_value(new)
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        let patch = merge_patch(&old, &new);
+
         self.inner = match self.schema_url {
-            Some(ref url) => self.inner.data_with_schema("application/json", url.to_string(), value),
-            None => self.inner.data("application/json", value),
+            Some(ref url) => self.inner.data_with_schema(MERGE_PATCH_CONTENT_TYPE, url.to_string(), patch),
+            None => self.inner.data(MERGE_PATCH_CONTENT_TYPE, patch),
         };
         self.inner = self.inner.ty(E::event_type());
 
-        Ok(
-            Event::new(
-                self.inner.build()
-                    .map_err(|e| Error::Unknown(anyhow!(e)))?
+        self.finish()
+    }
+
+    /// Build the event with JSON data that has no [`EventData`] type, e.g. when relaying
+    /// events of dynamic schemas.
+    /// 
+    /// # Arguments
+    /// * `event_type` - The type of the event, which [`EventBuilder::build`] takes from
+    ///   the data type.
+    /// * `value` - The JSON data of the event.
+    /// 
+    /// # Returns
+    /// A result containing the event or an error.
+    pub fn build_value(mut self, event_type: &str, value: Value) -> Result<Event> {
+        if self.schema_url.is_none() {
+            let resolved = self.schema_resolver
+                .as_ref()
+                .and_then(|resolver| resolver.resolve(event_type));
+
+            self = self.maybe_schema_url(resolved);
+        }
+
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
+        let value = if self.serde_options.snake_case_keys {
+            snake_case_keys(value)
+        } else {
+            value
+        };
+        let data = if self.serde_options.pretty {
+            CloudEventData::Binary(
+                to_vec_pretty(&value)
+                    .map_err(|e| Error::Serialization(e.to_string()))?
             )
-        )
+        } else {
+            CloudEventData::Json(value)
+        };
+
+        self.inner = match self.schema_url {
+            Some(ref url) => self.inner.data_with_schema("application/json", url.to_string(), data),
+            None => self.inner.data("application/json", data),
+        };
+        self.inner = self.inner.ty(event_type);
+
+        self.finish()
+    }
+
+    /// Build the event with string data stored as is, so [`Event::data_as_string`]
+    /// returns it verbatim, without a serialization round-trip.
+    /// 
+    /// # Arguments
+    /// * `event_type` - The type of the event.
+    /// * `data` - The data of the event.
+    /// * `content_type` - The content type of the data, e.g. `application/json`.
+    /// 
+    /// # Returns
+    /// A result containing the event or an error.
+    pub fn build_string(mut self, event_type: &str, data: String, content_type: &str) -> Result<Event> {
+        if self.schema_url.is_none() {
+            let resolved = self.schema_resolver
+                .as_ref()
+                .and_then(|resolver| resolver.resolve(event_type));
+
+            self = self.maybe_schema_url(resolved);
+        }
+
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
+        self.inner = match self.schema_url {
+            Some(ref url) => self.inner.data_with_schema(content_type, url.to_string(), data),
+            None => self.inner.data(content_type, data),
+        };
+        self.inner = self.inner.ty(event_type);
+
+        self.finish()
     }
 
     pub fn build_raw(mut self, data: Vec<u8>) -> Result<Event> {
@@ -339,12 +1064,68 @@ impl EventBuilder {
             None => self.inner.data("application/json", data),
         };
 
-        Ok(
-            Event::new(
-                self.inner.build()
-                    .map_err(|e| Error::Unknown(anyhow!(e)))?
-            )
-        )
+        self.finish()
+    }
+
+    /// Build the event with data already serialized by the caller, e.g. as protobuf or
+    /// avro, under the given content type.
+    /// 
+    /// # Arguments
+    /// * `event_type` - The type of the event.
+    /// * `data` - The serialized data of the event.
+    /// * `content_type` - The content type of the data.
+    /// 
+    /// # Returns
+    /// A result containing the event or an error.
+    pub fn build_raw_with_content_type(mut self, event_type: &str, data: Vec<u8>, content_type: &str) -> Result<Event> {
+        if self.schema_url.is_none() {
+            let resolved = self.schema_resolver
+                .as_ref()
+                .and_then(|resolver| resolver.resolve(event_type));
+
+            self = self.maybe_schema_url(resolved);
+        }
+
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
+        self.inner = match self.schema_url {
+            Some(ref url) => self.inner.data_with_schema(content_type, url.to_string(), data),
+            None => self.inner.data(content_type, data),
+        };
+        self.inner = self.inner.ty(event_type);
+
+        self.finish()
+    }
+
+    /// Build the event with the data it was pre-populated with by
+    /// [`EventBuilder::from_event`], if any, under the schema URL set on the builder.
+    pub fn rebuild(mut self) -> Result<Event> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
+        if let (Some(url), Some((content_type, data))) = (&self.schema_url, self.data.take()) {
+            let content_type = content_type.unwrap_or_else(|| "application/json".to_string());
+
+            self.inner = self.inner.data_with_schema(content_type, url.to_string(), data);
+        }
+
+        self.finish()
+    }
+
+    fn finish(self) -> Result<Event> {
+        let event = Event::new(
+            self.inner.build()
+                .map_err(|e| Error::Unknown(anyhow!(e)))?
+        );
+
+        if self.strict {
+            validate_strict(&event)?;
+        }
+
+        Ok(event)
     }
 }
 
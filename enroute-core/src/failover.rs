@@ -0,0 +1,174 @@
+use async_trait::async_trait;
+
+use crate::{
+    error::{Error, Result},
+    event::Event,
+    admin::AnyAdmin,
+    broker::{Broker, AnyBroker},
+    publisher::{Publisher, AnyPublisher, PublisherOptions},
+    consumer::{AnyConsumer, ConsumerOptions},
+};
+
+
+/// A publisher that fails over to the next of an ordered list of publishers when
+/// publishing fails.
+///
+/// Each event is published with the first publisher that succeeds, trying the others in
+/// order after an [`Error::Publisher`]. Other errors, such as serialization errors, fail
+/// the same way on every publisher and are returned right away.
+pub struct FailoverPublisher {
+    publishers: Vec<AnyPublisher>,
+}
+
+impl FailoverPublisher {
+    /// Create a new [`FailoverPublisher`].
+    ///
+    /// # Arguments
+    /// * `publishers` - The publishers to try, in order, starting with the primary.
+    ///
+    /// # Returns
+    /// The new [`FailoverPublisher`].
+    pub fn new(publishers: Vec<AnyPublisher>) -> Self {
+        Self { publishers }
+    }
+
+    /// Returns the publishers, in the order they are tried.
+    pub fn publishers(&self) -> &[AnyPublisher] {
+        &self.publishers
+    }
+
+    async fn publish_with<'a, F, Fut>(&'a self, event: Event, publish: F) -> Result<()>
+    where
+        F: Fn(&'a AnyPublisher, Event) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let mut failures = Vec::new();
+
+        for publisher in &self.publishers {
+            match publish(publisher, event.clone()).await {
+                Ok(()) => return Ok(()),
+                Err(Error::Publisher(e)) => failures.push(e),
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(Error::Publisher(match failures.is_empty() {
+            true => "no publisher to fail over to".to_string(),
+            false => format!("all publishers failed: {}", failures.join("; ")),
+        }))
+    }
+}
+
+#[async_trait]
+impl Publisher for FailoverPublisher {
+    async fn publish_event(&self, event: Event) -> Result<()> {
+        self.publish_with(event, |publisher, event| publisher.publish_event(event))
+            .await
+    }
+
+    async fn publish_event_to(&self, channel: &str, event: Event) -> Result<()> {
+        self.publish_with(event, |publisher, event| publisher.publish_event_to(channel, event))
+            .await
+    }
+
+    /// Returns the channel of the primary publisher.
+    fn channel(&self) -> &str {
+        self.publishers
+            .first()
+            .map(|publisher| publisher.channel())
+            .unwrap_or_default()
+    }
+
+    fn strict_channel_check(&self) -> bool {
+        self.publishers
+            .first()
+            .is_some_and(|publisher| publisher.strict_channel_check())
+    }
+}
+
+
+/// A broker creating [`FailoverPublisher`]s over an ordered list of brokers.
+///
+/// Publishers are created on every broker that can create one, skipping brokers that are
+/// down, in order. Consumers and admin handles are created on the first broker that can
+/// create them, so events failed over to a secondary broker must be consumed from it
+/// separately.
+pub struct FailoverBroker {
+    brokers: Vec<AnyBroker>,
+}
+
+impl FailoverBroker {
+    /// Create a new [`FailoverBroker`].
+    ///
+    /// # Arguments
+    /// * `brokers` - The brokers to fail over between, in order, starting with the primary.
+    ///
+    /// # Returns
+    /// The new [`FailoverBroker`].
+    pub fn new(brokers: Vec<AnyBroker>) -> Self {
+        Self { brokers }
+    }
+
+    /// Returns the brokers, in the order they are tried.
+    pub fn brokers(&self) -> &[AnyBroker] {
+        &self.brokers
+    }
+}
+
+#[async_trait]
+impl Broker for FailoverBroker {
+    type Publisher = FailoverPublisher;
+    type Consumer = AnyConsumer;
+    type Admin = AnyAdmin;
+
+    async fn publisher(&self, options: PublisherOptions) -> Result<Self::Publisher> {
+        let mut publishers = Vec::new();
+        let mut last_error = None;
+
+        for broker in &self.brokers {
+            match broker.publisher(options.clone()).await {
+                Ok(publisher) => publishers.push(publisher),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        match (publishers.is_empty(), last_error) {
+            (true, Some(e)) => Err(e),
+            (true, None) => Err(Error::Builder("no broker to fail over to".to_string())),
+            (false, _) => Ok(FailoverPublisher::new(publishers)),
+        }
+    }
+
+    async fn consumer(&self, options: ConsumerOptions) -> Result<Self::Consumer> {
+        let mut last_error = None;
+
+        for broker in &self.brokers {
+            match broker.consumer(options.clone()).await {
+                Ok(consumer) => return Ok(consumer),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| Error::Builder("no broker to fail over to".to_string())))
+    }
+
+    async fn channels(&self) -> Result<Vec<String>> {
+        match self.brokers.first() {
+            Some(broker) => broker.channels().await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn admin(&self) -> Result<Self::Admin> {
+        let mut last_error = None;
+
+        for broker in &self.brokers {
+            match broker.admin().await {
+                Ok(admin) => return Ok(admin),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| Error::Unsupported("topic management".to_string())))
+    }
+}
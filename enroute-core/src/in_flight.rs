@@ -0,0 +1,112 @@
+use std::{pin::Pin, sync::{Arc, Mutex}, fmt::{self, Debug}, time::Duration};
+use async_trait::async_trait;
+use futures::{Stream, StreamExt, stream};
+use mea::semaphore::{Semaphore, OwnedSemaphorePermit};
+
+use crate::{error::Result, envelope::{Acker, Envelope}};
+
+
+/// An acker that holds an in-flight permit until the envelope is settled.
+struct InFlightAcker {
+    inner: Arc<dyn Acker>,
+    permit: Mutex<Option<OwnedSemaphorePermit>>,
+}
+
+impl InFlightAcker {
+    fn release(&self) {
+        self.permit
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+    }
+}
+
+impl Debug for InFlightAcker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InFlightAcker")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Acker for InFlightAcker {
+    async fn ack(&self) {
+        self.inner.ack().await;
+        self.release();
+    }
+
+    async fn nack(&self) {
+        self.inner.nack().await;
+        self.release();
+    }
+
+    async fn extend_deadline(&self, by: Duration) -> Result<()> {
+        self.inner.extend_deadline(by).await
+    }
+}
+
+
+/// Limit the number of unsettled envelopes yielded by a stream.
+///
+/// Once `max` envelopes are outstanding, the stream waits for one of them to be acked
+/// or nacked before pulling the next envelope from the inner stream. An envelope that is
+/// dropped without being settled also frees its slot once all of its clones are dropped.
+///
+/// # Arguments
+/// * `envelopes` - The stream of envelopes to limit.
+/// * `max` - The maximum number of unsettled envelopes, at least one.
+///
+/// # Returns
+/// The limited stream of envelopes.
+pub fn limit_in_flight(
+    envelopes: Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>,
+    max: usize,
+) -> Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>> {
+    let semaphore = Arc::new(Semaphore::new(max.max(1)));
+
+    Box::pin(stream::unfold((envelopes, semaphore), |(mut envelopes, semaphore)| async move {
+        let permit = semaphore.clone().acquire_owned(1).await;
+        let next = envelopes
+            .next()
+            .await?
+            .map(|envelope| envelope.map_acker(|acker| Arc::new(InFlightAcker {
+                inner: acker,
+                permit: Mutex::new(Some(permit)),
+            })));
+
+        Some((next, (envelopes, semaphore)))
+    }))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+
+    use super::*;
+    use crate::event::EventBuilder;
+
+    fn envelopes(count: usize) -> Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>> {
+        Box::pin(stream::iter((0..count).map(|_| {
+            EventBuilder::new()
+                .source("test")
+                .type_("test")
+                .build_raw(b"{}".to_vec())
+                .map(Envelope::noop)
+        })))
+    }
+
+    #[tokio::test]
+    async fn holds_back_envelopes_beyond_the_limit_until_one_is_settled() {
+        let mut limited = limit_in_flight(envelopes(3), 2);
+        let first = limited.next().await.unwrap().unwrap();
+        let _second = limited.next().await.unwrap().unwrap();
+
+        let mut third = limited.next();
+        assert!((&mut third).now_or_never().is_none());
+
+        first.ack().await;
+        assert!(third.await.unwrap().is_ok());
+    }
+}
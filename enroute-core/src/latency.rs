@@ -0,0 +1,267 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug},
+    pin::Pin,
+    sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}},
+    time::{Duration, Instant},
+};
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+
+use crate::{
+    error::Result,
+    envelope::{Acker, Envelope},
+    consumer::{Consumer, ConsumerEvent},
+};
+
+
+/// The number of buckets each power of two is split into, bounding the relative error
+/// of reported latencies to 1/8.
+const SUB_BUCKETS: u64 = 8;
+const SUB_BUCKET_BITS: u32 = SUB_BUCKETS.trailing_zeros();
+const BUCKETS: usize = (SUB_BUCKETS + (64 - SUB_BUCKET_BITS as u64) * SUB_BUCKETS) as usize;
+
+/// A log-linear histogram of latencies in microseconds, with constant memory whatever
+/// the number of samples.
+#[derive(Clone)]
+struct Histogram {
+    counts: Vec<u64>,
+    total: u64,
+    max: u64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            counts: vec![0; BUCKETS],
+            total: 0,
+            max: 0,
+        }
+    }
+
+    fn index(value: u64) -> usize {
+        if value < SUB_BUCKETS {
+            return value as usize;
+        }
+
+        let magnitude = 63 - value.leading_zeros();
+        let sub_bucket = (value >> (magnitude - SUB_BUCKET_BITS)) - SUB_BUCKETS;
+
+        (SUB_BUCKETS + u64::from(magnitude - SUB_BUCKET_BITS) * SUB_BUCKETS + sub_bucket) as usize
+    }
+
+    fn upper_bound(index: usize) -> u64 {
+        let index = index as u64;
+
+        if index < SUB_BUCKETS {
+            return index;
+        }
+
+        let shift = (index - SUB_BUCKETS) / SUB_BUCKETS;
+        let sub_bucket = (index - SUB_BUCKETS) % SUB_BUCKETS;
+        let lower = (SUB_BUCKETS + sub_bucket) << shift;
+
+        lower.saturating_add((1 << shift) - 1)
+    }
+
+    fn record(&mut self, value: u64) {
+        self.counts[Self::index(value)] += 1;
+        self.total += 1;
+        self.max = self.max.max(value);
+    }
+
+    fn percentile(&self, quantile: f64) -> u64 {
+        let target = ((quantile * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+
+            if seen >= target {
+                return Self::upper_bound(index).min(self.max);
+            }
+        }
+
+        self.max
+    }
+
+    fn summary(&self) -> LatencySummary {
+        LatencySummary {
+            count: self.total,
+            p50: Duration::from_micros(self.percentile(0.50)),
+            p95: Duration::from_micros(self.percentile(0.95)),
+            p99: Duration::from_micros(self.percentile(0.99)),
+            max: Duration::from_micros(self.max),
+        }
+    }
+}
+
+type Histograms = Arc<Mutex<HashMap<String, Histogram>>>;
+
+/// A summary of the latencies of one event type, from receipt to settlement.
+///
+/// Percentiles are accurate to within 1/8 of their value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencySummary {
+    /// The number of settled envelopes.
+    pub count: u64,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+
+/// An acker recording the time from receipt to the first ack or nack.
+struct LatencyAcker {
+    inner: Arc<dyn Acker>,
+    event_type: String,
+    received: Instant,
+    histograms: Histograms,
+    settled: AtomicBool,
+}
+
+impl LatencyAcker {
+    fn record(&self) {
+        if self.settled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let elapsed = u64::try_from(self.received.elapsed().as_micros()).unwrap_or(u64::MAX);
+
+        self.histograms
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(self.event_type.clone())
+            .or_insert_with(Histogram::new)
+            .record(elapsed);
+    }
+}
+
+impl Debug for LatencyAcker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LatencyAcker")
+            .field("inner", &self.inner)
+            .field("event_type", &self.event_type)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Acker for LatencyAcker {
+    async fn ack(&self) {
+        self.inner.ack().await;
+        self.record();
+    }
+
+    async fn nack(&self) {
+        self.inner.nack().await;
+        self.record();
+    }
+
+    async fn extend_deadline(&self, by: Duration) -> Result<()> {
+        self.inner.extend_deadline(by).await
+    }
+}
+
+
+/// A consumer measuring the latency of handling its envelopes, per event type.
+///
+/// The latency of an envelope runs from when the stream yields it to when it is first
+/// acked or nacked, including the settlement itself. Envelopes dropped without being
+/// settled are not measured.
+pub struct LatencyTrackingConsumer<C: Consumer> {
+    inner: C,
+    histograms: Histograms,
+}
+
+impl<C: Consumer> LatencyTrackingConsumer<C> {
+    /// Create a new [`LatencyTrackingConsumer`] wrapping the given consumer.
+    ///
+    /// # Arguments
+    /// * `inner` - The consumer to measure envelopes of.
+    ///
+    /// # Returns
+    /// The new [`LatencyTrackingConsumer`].
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            histograms: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns a summary of the latencies measured so far, keyed by event type.
+    pub fn latencies(&self) -> HashMap<String, LatencySummary> {
+        self.histograms
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(event_type, histogram)| (event_type.clone(), histogram.summary()))
+            .collect()
+    }
+
+    /// Discard the latencies measured so far, e.g. to report them per interval.
+    pub fn reset(&self) {
+        self.histograms
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Returns a reference to the inner consumer.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    fn track(envelope: Envelope, histograms: &Histograms) -> Envelope {
+        let event_type = envelope.event().type_().to_string();
+
+        envelope.map_acker(|acker| Arc::new(LatencyAcker {
+            inner: acker,
+            event_type,
+            received: Instant::now(),
+            histograms: histograms.clone(),
+            settled: AtomicBool::new(false),
+        }))
+    }
+}
+
+#[async_trait]
+impl<C: Consumer> Consumer for LatencyTrackingConsumer<C> {
+    async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
+        let histograms = self.histograms.clone();
+
+        Ok(Box::pin(
+            self.inner
+                .stream_events()
+                .await?
+                .map(move |envelope| envelope.map(|envelope| Self::track(envelope, &histograms)))
+        ))
+    }
+
+    async fn pause(&self) -> Result<()> {
+        self.inner.pause().await
+    }
+
+    async fn resume(&self) -> Result<()> {
+        self.inner.resume().await
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    async fn stream_with_control(&self) -> Result<Pin<Box<dyn Stream<Item = Result<ConsumerEvent>> + Send>>> {
+        let histograms = self.histograms.clone();
+
+        Ok(Box::pin(
+            self.inner
+                .stream_with_control()
+                .await?
+                .map(move |event| event.map(|event| match event {
+                    ConsumerEvent::Message(envelope) => ConsumerEvent::Message(Self::track(envelope, &histograms)),
+                    event => event,
+                }))
+        ))
+    }
+}
@@ -3,7 +3,51 @@ extern crate self as enroute_core;
 
 pub mod error;
 pub mod event;
+pub mod clock;
+pub mod codec;
+#[cfg(feature = "broker")]
 pub mod envelope;
+#[cfg(feature = "broker")]
 pub mod publisher;
+#[cfg(feature = "broker")]
 pub mod consumer;
-pub mod broker;
\ No newline at end of file
+#[cfg(feature = "broker")]
+pub mod broker;
+#[cfg(feature = "broker")]
+pub mod admin;
+#[cfg(feature = "broker")]
+pub mod sampling;
+#[cfg(feature = "broker")]
+pub mod window;
+#[cfg(feature = "broker")]
+pub mod replay;
+#[cfg(feature = "broker")]
+pub mod in_flight;
+pub mod registry;
+pub mod versioning;
+#[cfg(feature = "broker")]
+pub mod typed;
+#[cfg(feature = "broker")]
+pub mod reorder;
+#[cfg(feature = "broker")]
+pub mod rate_limit;
+#[cfg(feature = "broker")]
+pub mod pause;
+#[cfg(feature = "broker")]
+pub mod catching;
+#[cfg(feature = "broker")]
+pub mod rpc;
+#[cfg(feature = "broker")]
+pub mod failover;
+#[cfg(feature = "broker")]
+pub mod latency;
+#[cfg(feature = "broker")]
+pub mod outbox;
+#[cfg(feature = "broker")]
+pub mod splitter;
+#[cfg(feature = "broker")]
+pub mod ttl;
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "sse")]
+pub mod sse;
\ No newline at end of file
@@ -0,0 +1,299 @@
+use std::{collections::VecDeque, future::Future, pin::pin, sync::{Arc, Mutex}, time::Duration};
+use async_trait::async_trait;
+use futures::future::{self, Either};
+use futures_timer::Delay;
+use uuid::Uuid;
+
+use crate::{
+    error::Result,
+    event::Event,
+    publisher::Publisher,
+};
+
+
+/// The default number of entries an [`OutboxRelay`] publishes per batch.
+const DEFAULT_BATCH_SIZE: usize = 100;
+/// The default time an [`OutboxRelay`] waits before polling an empty store again.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An event staged in an [`OutboxStore`], with the channel it is to be published to.
+#[derive(Debug, Clone)]
+pub struct OutboxEntry {
+    /// The id of the entry, unique within its store.
+    pub id: String,
+    /// The channel to publish the event to.
+    pub channel: String,
+    /// The event to publish.
+    pub event: Event,
+}
+
+impl OutboxEntry {
+    /// Create a new [`OutboxEntry`] with a random id.
+    ///
+    /// # Arguments
+    /// * `channel` - The channel to publish the event to.
+    /// * `event` - The event to publish.
+    ///
+    /// # Returns
+    /// The new [`OutboxEntry`].
+    pub fn new(channel: impl Into<String>, event: Event) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            channel: channel.into(),
+            event,
+        }
+    }
+}
+
+/// A store of events waiting to be published, e.g. a table written in the same database
+/// transaction as the state change the events describe.
+#[async_trait]
+pub trait OutboxStore: Send + Sync {
+    /// Stage an entry to be published.
+    ///
+    /// # Arguments
+    /// * `entry` - The entry to stage.
+    ///
+    /// # Returns
+    /// A result indicating success or failure.
+    async fn enqueue(&self, entry: OutboxEntry) -> Result<()>;
+    /// Returns the oldest entries not yet marked as sent, in the order they were staged,
+    /// without removing them.
+    ///
+    /// # Arguments
+    /// * `limit` - The maximum number of entries to return.
+    ///
+    /// # Returns
+    /// A result containing the entries or an error.
+    async fn dequeue(&self, limit: usize) -> Result<Vec<OutboxEntry>>;
+    /// Mark an entry as sent, so it is not returned by [`OutboxStore::dequeue`] again.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the entry.
+    ///
+    /// # Returns
+    /// A result indicating success or failure.
+    async fn mark_sent(&self, id: &str) -> Result<()>;
+}
+
+/// An in-memory [`OutboxStore`], for tests.
+#[derive(Debug, Default)]
+pub struct InMemoryOutboxStore {
+    entries: Mutex<VecDeque<OutboxEntry>>,
+}
+
+impl InMemoryOutboxStore {
+    /// Create a new empty [`InMemoryOutboxStore`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the entries not yet marked as sent.
+    pub fn pending(&self) -> Vec<OutboxEntry> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the number of entries not yet marked as sent.
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+
+    /// Returns whether every entry has been marked as sent.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl OutboxStore for InMemoryOutboxStore {
+    async fn enqueue(&self, entry: OutboxEntry) -> Result<()> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push_back(entry);
+        Ok(())
+    }
+
+    async fn dequeue(&self, limit: usize) -> Result<Vec<OutboxEntry>> {
+        Ok(
+            self.entries
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .take(limit)
+                .cloned()
+                .collect()
+        )
+    }
+
+    async fn mark_sent(&self, id: &str) -> Result<()> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|entry| entry.id != id);
+        Ok(())
+    }
+}
+
+
+/// A publisher staging events in an [`OutboxStore`] instead of publishing them, to be
+/// published by an [`OutboxRelay`].
+pub struct OutboxPublisher<S: OutboxStore> {
+    store: Arc<S>,
+    channel: String,
+}
+
+impl<S: OutboxStore> OutboxPublisher<S> {
+    /// Create a new [`OutboxPublisher`].
+    ///
+    /// # Arguments
+    /// * `store` - The store to stage events in.
+    /// * `channel` - The channel events are published to by default.
+    ///
+    /// # Returns
+    /// The new [`OutboxPublisher`].
+    pub fn new(store: Arc<S>, channel: impl Into<String>) -> Self {
+        Self {
+            store,
+            channel: channel.into(),
+        }
+    }
+
+    /// Returns a reference to the store events are staged in.
+    pub fn store(&self) -> &Arc<S> {
+        &self.store
+    }
+}
+
+#[async_trait]
+impl<S: OutboxStore> Publisher for OutboxPublisher<S> {
+    async fn publish_event(&self, event: Event) -> Result<()> {
+        self.store
+            .enqueue(OutboxEntry::new(self.channel.as_str(), event))
+            .await
+    }
+
+    async fn publish_event_to(&self, channel: &str, event: Event) -> Result<()> {
+        self.store
+            .enqueue(OutboxEntry::new(channel, event))
+            .await
+    }
+
+    fn channel(&self) -> &str {
+        &self.channel
+    }
+}
+
+
+/// A relay publishing the entries staged in an [`OutboxStore`] with a publisher.
+///
+/// Entries are published in the order they were staged and marked as sent once
+/// published, so an entry whose publishing fails is retried, and one published before the
+/// relay fails to mark it is published again: delivery is at least once. When publishing
+/// an entry fails, the rest of its batch is left for the next poll to keep the order.
+pub struct OutboxRelay<S: OutboxStore, P: Publisher> {
+    store: Arc<S>,
+    publisher: P,
+    batch_size: usize,
+    poll_interval: Duration,
+}
+
+impl<S: OutboxStore, P: Publisher> OutboxRelay<S, P> {
+    /// Create a new [`OutboxRelay`].
+    ///
+    /// # Arguments
+    /// * `store` - The store to publish the entries of.
+    /// * `publisher` - The publisher to publish the entries with, which must support
+    ///   [`Publisher::publish_event_to`].
+    ///
+    /// # Returns
+    /// The new [`OutboxRelay`].
+    pub fn new(store: Arc<S>, publisher: P) -> Self {
+        Self {
+            store,
+            publisher,
+            batch_size: DEFAULT_BATCH_SIZE,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Set the maximum number of entries published per batch, 100 by default.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Set how long to wait before polling the store again once it is drained or
+    /// publishing fails, one second by default.
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Publish a batch of staged entries.
+    ///
+    /// # Returns
+    /// A result containing the number of entries published, or the first error.
+    pub async fn relay_once(&self) -> Result<usize> {
+        let entries = self.store
+            .dequeue(self.batch_size)
+            .await?;
+        let mut published = 0;
+
+        for entry in entries {
+            self.publisher
+                .publish_event_to(&entry.channel, entry.event)
+                .await?;
+            self.store
+                .mark_sent(&entry.id)
+                .await?;
+
+            published += 1;
+        }
+
+        Ok(published)
+    }
+
+    /// Publish staged entries until the shutdown future completes, polling the store
+    /// every poll interval once it is drained.
+    ///
+    /// Errors are retried after the poll interval, so the relay only stops on shutdown.
+    ///
+    /// # Arguments
+    /// * `shutdown` - A future that completes when the relay should stop.
+    pub async fn run_until_shutdown<F>(&self, shutdown: F)
+    where
+        F: Future<Output = ()> + Send,
+    {
+        let mut shutdown = pin!(shutdown);
+
+        loop {
+            let relayed = match future::select(pin!(self.relay_once()), shutdown.as_mut()).await {
+                Either::Left((relayed, _)) => relayed,
+                Either::Right(_) => return,
+            };
+
+            // Keep draining full batches without waiting.
+            if matches!(relayed, Ok(count) if count == self.batch_size) {
+                continue;
+            }
+
+            if let Either::Right(_) = future::select(Delay::new(self.poll_interval), shutdown.as_mut()).await {
+                return;
+            }
+        }
+    }
+
+    /// Returns a reference to the publisher entries are published with.
+    pub fn publisher(&self) -> &P {
+        &self.publisher
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+use futures::{Stream, StreamExt, stream};
+use mea::{condvar::Condvar, mutex::Mutex};
+
+
+/// A shared flag pausing and resuming the streams it gates.
+///
+/// Clones share the same flag, so a consumer can keep one clone to gate its streams
+/// while pausing and resuming through another.
+#[derive(Debug, Clone, Default)]
+pub struct PauseGate {
+    paused: Arc<Mutex<bool>>,
+    resumed: Arc<Condvar>,
+}
+
+impl PauseGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pause the gated streams. Items already yielded are unaffected.
+    pub async fn pause(&self) {
+        *self.paused.lock().await = true;
+    }
+
+    /// Resume the gated streams.
+    pub async fn resume(&self) {
+        *self.paused.lock().await = false;
+        self.resumed.notify_all();
+    }
+
+    /// Returns whether the gate is paused.
+    pub async fn is_paused(&self) -> bool {
+        *self.paused.lock().await
+    }
+
+    /// Wait until the gate is not paused.
+    pub async fn wait(&self) {
+        let _ = self.resumed
+            .wait_while(self.paused.lock().await, |paused| *paused)
+            .await;
+    }
+
+    /// Gate a stream, so that no item is pulled from it while the gate is paused.
+    ///
+    /// # Arguments
+    /// * `inner` - The stream to gate.
+    ///
+    /// # Returns
+    /// The gated stream.
+    pub fn gate<S>(&self, inner: S) -> impl Stream<Item = S::Item> + Send + use<S>
+    where
+        S: Stream + Send + Unpin,
+    {
+        stream::unfold((inner, self.clone()), |(mut inner, gate)| async move {
+            gate.wait().await;
+
+            inner
+                .next()
+                .await
+                .map(|item| (item, (inner, gate)))
+        })
+    }
+}
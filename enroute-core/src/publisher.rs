@@ -1,8 +1,8 @@
-use std::sync::Arc;
+use std::{any::Any, sync::Arc, collections::HashMap, future::Future};
 use async_trait::async_trait;
 use serde::{Serialize, Deserialize};
 
-use crate::{error::Result, event::Event};
+use crate::{error::{Error, Result}, event::{Event, EventBuilder, EventData, ExtensionValue}};
 
 
 /// Options for configuring a publisher.
@@ -10,6 +10,16 @@ use crate::{error::Result, event::Event};
 pub struct PublisherOptions {
     /// The channel to publish messages to.
     pub channel: String,
+    /// Backend-specific settings, merged into the backend's client configuration.
+    #[serde(default)]
+    pub extra: HashMap<String, String>,
+    /// Extensions applied to every published event that doesn't already carry them.
+    #[serde(default)]
+    pub default_extensions: HashMap<String, ExtensionValue>,
+    /// Whether [`Publisher::publish_typed`] rejects event data whose
+    /// [`EventData::channel_name`] differs from the publisher's channel.
+    #[serde(default)]
+    pub strict_channel_check: bool,
 }
 
 impl PublisherOptions {
@@ -23,6 +33,9 @@ impl PublisherOptions {
 #[derive(Default, Debug, Clone)]
 pub struct PublisherOptionsBuilder {
     channel: Option<String>,
+    extra: HashMap<String, String>,
+    default_extensions: HashMap<String, ExtensionValue>,
+    strict_channel_check: bool,
 }
 
 impl PublisherOptionsBuilder {
@@ -43,17 +56,96 @@ impl PublisherOptionsBuilder {
         self
     }
 
+    /// Set a backend-specific setting.
+    /// 
+    /// # Arguments
+    /// * `key` - The name of the setting, as understood by the backend.
+    /// * `value` - The value of the setting.
+    /// 
+    /// # Returns
+    /// The builder with the setting added.
+    pub fn extra(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.extra.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set multiple backend-specific settings.
+    /// 
+    /// # Arguments
+    /// * `extra` - The settings to add, as understood by the backend.
+    /// 
+    /// # Returns
+    /// The builder with the settings added.
+    pub fn extras(mut self, extra: HashMap<impl Into<String>, impl Into<String>>) -> Self {
+        self.extra.extend(extra.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Set an extension applied to every published event that doesn't already carry it.
+    /// 
+    /// # Arguments
+    /// * `name` - The name of the extension.
+    /// * `value` - The default value of the extension.
+    /// 
+    /// # Returns
+    /// The builder with the default extension added.
+    pub fn default_extension(mut self, name: impl Into<String>, value: impl Into<ExtensionValue>) -> Self {
+        self.default_extensions.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set multiple extensions applied to every published event that doesn't already carry them.
+    /// 
+    /// # Arguments
+    /// * `extensions` - The default extensions to add.
+    /// 
+    /// # Returns
+    /// The builder with the default extensions added.
+    pub fn default_extensions(mut self, extensions: HashMap<impl Into<String>, impl Into<ExtensionValue>>) -> Self {
+        self.default_extensions.extend(extensions.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Set whether [`Publisher::publish_typed`] rejects event data whose
+    /// [`EventData::channel_name`] differs from the publisher's channel, to catch
+    /// routing mistakes. Leave it disabled when events are routed across channels.
+    /// 
+    /// # Arguments
+    /// * `strict` - Whether to check the channel of typed events.
+    /// 
+    /// # Returns
+    /// The builder with the check set.
+    pub fn strict_channel_check(mut self, strict: bool) -> Self {
+        self.strict_channel_check = strict;
+        self
+    }
+
     /// Build the [`PublisherOptions`] from the builder.
     /// 
     /// # Returns
     /// The built [`PublisherOptions`].
     /// 
     /// # Panics
-    /// If the channel is not set.
+    /// If the channel is not set. Use [`PublisherOptionsBuilder::try_build`] when the
+    /// options come from dynamic configuration.
     pub fn build(self) -> PublisherOptions {
-        PublisherOptions {
-            channel: self.channel.expect("channel is required"),
-        }
+        self.try_build()
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Try to build the [`PublisherOptions`] from the builder.
+    /// 
+    /// # Returns
+    /// A result containing the built [`PublisherOptions`], or an [`Error::Builder`]
+    /// if the channel is not set.
+    pub fn try_build(self) -> Result<PublisherOptions> {
+        Ok(PublisherOptions {
+            channel: self.channel
+                .ok_or_else(|| Error::Builder("channel is required".to_string()))?,
+            extra: self.extra,
+            default_extensions: self.default_extensions,
+            strict_channel_check: self.strict_channel_check,
+        })
     }
 }
 
@@ -68,29 +160,127 @@ pub trait Publisher: Send + Sync {
     /// # Returns
     /// A result indicating success or failure.
     async fn publish_event(&self, event: Event) -> Result<()>;
+    /// Publish an event to the given channel instead of the publisher's own channel.
+    /// 
+    /// The default implementation fails with [`Error::Unsupported`], for publishers
+    /// bound to their channel.
+    /// 
+    /// # Arguments
+    /// * `channel` - The channel to publish the event to.
+    /// * `event` - The event to be published.
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    async fn publish_event_to(&self, _channel: &str, _event: Event) -> Result<()> {
+        Err(Error::Unsupported("publishing to another channel".to_string()))
+    }
+    /// Returns the channel the publisher publishes to.
+    fn channel(&self) -> &str;
+    /// Returns whether [`Publisher::publish_typed`] checks the channel of the event data
+    /// against the publisher's channel, as set by
+    /// [`PublisherOptions::strict_channel_check`].
+    fn strict_channel_check(&self) -> bool {
+        false
+    }
+    /// Build an event from typed data and publish it.
+    /// 
+    /// With [`Publisher::strict_channel_check`] enabled, data whose
+    /// [`EventData::channel_name`] differs from the publisher's channel is rejected with
+    /// an [`Error::Publisher`] instead of being published.
+    /// 
+    /// # Arguments
+    /// * `builder` - The builder of the event, with its attributes set.
+    /// * `data` - The data of the event.
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    async fn publish_typed<E: EventData>(&self, builder: EventBuilder, data: E) -> Result<()>
+    where
+        Self: Sized,
+    {
+        if self.strict_channel_check() && E::channel_name() != self.channel() {
+            return Err(Error::Publisher(format!(
+                "event type {} belongs to channel {}, not {}",
+                E::event_type(),
+                E::channel_name(),
+                self.channel(),
+            )));
+        }
+
+        self.publish_event(builder.build(data)?).await
+    }
+}
+
+/// A publisher whose publish future is returned unboxed, for hot publish paths.
+/// 
+/// [`Publisher::publish_event`] boxes its future to keep the trait object safe, costing
+/// an allocation per call. Code generic over a concrete publisher can bound on this trait
+/// instead to avoid it, at the cost of not being usable as a trait object.
+pub trait NativePublisher: Send + Sync {
+    /// Publish an event to the message broker.
+    /// 
+    /// # Arguments
+    /// * `event` - The event to be published.
+    /// 
+    /// # Returns
+    /// A future resolving to a result indicating success or failure.
+    fn publish(&self, event: Event) -> impl Future<Output = Result<()>> + Send;
 }
 
 
 /// A type-erased publisher that can hold any concrete publisher implementation.
-pub struct AnyPublisher(Arc<dyn Publisher>);
+pub struct AnyPublisher {
+    inner: Arc<dyn Publisher>,
+    any: Arc<dyn Any + Send + Sync>,
+}
 
 impl AnyPublisher {
     pub fn new<P>(publisher: P) -> Self
     where
         P: Publisher + 'static,
     {
-        Self(Arc::new(publisher))
+        let publisher = Arc::new(publisher);
+
+        Self {
+            inner: publisher.clone(),
+            any: publisher,
+        }
     }
 
     pub fn into_inner(self) -> Arc<dyn Publisher> {
-        self.0
+        self.inner
+    }
+
+    /// Returns a reference to the concrete publisher if it is of type `P`.
+    /// 
+    /// # Returns
+    /// A reference to the concrete publisher, or `None` if it is of a different type.
+    pub fn downcast_ref<P: Publisher + 'static>(&self) -> Option<&P> {
+        self.any.downcast_ref::<P>()
+    }
+
+    /// Returns whether the concrete publisher is of type `P`.
+    pub fn is<P: Publisher + 'static>(&self) -> bool {
+        self.any.is::<P>()
     }
 }
 
 #[async_trait]
 impl Publisher for AnyPublisher {
     async fn publish_event(&self, event: Event) -> Result<()> {
-        self.0.publish_event(event).await
+        self.inner.publish_event(event).await
+    }
+
+    async fn publish_event_to(&self, channel: &str, event: Event) -> Result<()> {
+        self.inner.publish_event_to(channel, event).await
+    }
+
+    fn channel(&self) -> &str {
+        self.inner.channel()
+    }
+
+    fn strict_channel_check(&self) -> bool {
+        self.inner.strict_channel_check()
     }
 }
 
@@ -0,0 +1,121 @@
+use std::{pin::Pin, time::{Duration, Instant}};
+use async_trait::async_trait;
+use futures::{Stream, StreamExt, stream};
+use futures_timer::Delay;
+
+use crate::{
+    error::Result,
+    envelope::Envelope,
+    consumer::Consumer,
+};
+
+
+struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u32, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+
+        Self {
+            rate: f64::from(rate.max(1)),
+            burst,
+            tokens: burst,
+            refilled: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+
+        self.tokens = (self.tokens + now.duration_since(self.refilled).as_secs_f64() * self.rate).min(self.burst);
+        self.refilled = now;
+    }
+
+    async fn take(&mut self) {
+        self.refill();
+
+        if self.tokens < 1.0 {
+            Delay::new(Duration::from_secs_f64((1.0 - self.tokens) / self.rate)).await;
+            self.refill();
+        }
+
+        self.tokens = (self.tokens - 1.0).max(0.0);
+    }
+}
+
+/// A consumer that paces the envelopes of an inner consumer with a token bucket.
+///
+/// Envelopes are held back until a token is available rather than dropped, so only the
+/// throughput is limited and the delivery guarantees of the inner consumer still apply.
+/// The bucket starts full, allowing an initial burst.
+pub struct RateLimitedConsumer<C: Consumer> {
+    inner: C,
+    rate: u32,
+    burst: u32,
+}
+
+impl<C: Consumer> RateLimitedConsumer<C> {
+    /// Create a new [`RateLimitedConsumer`] wrapping the given consumer.
+    ///
+    /// # Arguments
+    /// * `inner` - The consumer to pace envelopes from.
+    /// * `rate` - The maximum number of envelopes emitted per second, at least 1.
+    ///
+    /// # Returns
+    /// The new [`RateLimitedConsumer`].
+    pub fn new(inner: C, rate: u32) -> Self {
+        Self { inner, rate, burst: 1 }
+    }
+
+    /// Set the number of envelopes that may be emitted at once after an idle period.
+    ///
+    /// # Arguments
+    /// * `burst` - The capacity of the token bucket, at least 1.
+    ///
+    /// # Returns
+    /// The consumer with the burst set.
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst;
+        self
+    }
+
+    /// Returns a reference to the inner consumer.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<C: Consumer> Consumer for RateLimitedConsumer<C> {
+    async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
+        let envelopes = self.inner.stream_events().await?;
+        let bucket = TokenBucket::new(self.rate, self.burst);
+
+        Ok(Box::pin(stream::unfold((envelopes, bucket), |(mut envelopes, mut bucket)| async move {
+            let next = envelopes.next().await?;
+
+            if next.is_ok() {
+                bucket.take().await;
+            }
+
+            Some((next, (envelopes, bucket)))
+        })))
+    }
+
+    async fn pause(&self) -> Result<()> {
+        self.inner.pause().await
+    }
+
+    async fn resume(&self) -> Result<()> {
+        self.inner.resume().await
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+}
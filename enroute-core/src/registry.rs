@@ -0,0 +1,59 @@
+use std::{any::Any, collections::HashMap};
+
+use crate::{error::{Error, Result}, event::{Event, EventData}};
+
+
+type Deserializer = Box<dyn Fn(&Event) -> Result<Box<dyn Any + Send + Sync>> + Send + Sync>;
+
+/// A registry mapping event types to their [`EventData`] types, for consumers that
+/// receive events of many types.
+#[derive(Default)]
+pub struct EventRegistry {
+    deserializers: HashMap<&'static str, Deserializer>,
+}
+
+impl EventRegistry {
+    /// Create a new empty [`EventRegistry`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an event data type under its event type, replacing any type
+    /// previously registered under the same event type.
+    ///
+    /// # Returns
+    /// The registry with the type registered.
+    pub fn register<E: EventData>(mut self) -> Self {
+        self.deserializers.insert(
+            E::event_type(),
+            Box::new(|event| Ok(Box::new(event.data::<E>()?))),
+        );
+        self
+    }
+
+    /// Returns whether a type is registered for the given event type.
+    pub fn is_registered(&self, event_type: &str) -> bool {
+        self.deserializers.contains_key(event_type)
+    }
+
+    /// Returns an iterator over the registered event types.
+    pub fn event_types(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.deserializers.keys().copied()
+    }
+
+    /// Deserialize the event data into the type registered for the event's type.
+    ///
+    /// # Arguments
+    /// * `event` - The event to deserialize the data of.
+    ///
+    /// # Returns
+    /// A result containing the boxed event data, to be downcast by the caller, or an
+    /// [`Error::Deserialization`] if no type is registered for the event's type.
+    pub fn deserialize(&self, event: &Event) -> Result<Box<dyn Any + Send + Sync>> {
+        let deserializer = self.deserializers
+            .get(event.type_())
+            .ok_or_else(|| Error::Deserialization(format!("Unregistered event type: {}", event.type_())))?;
+
+        deserializer(event)
+    }
+}
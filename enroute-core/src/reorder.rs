@@ -0,0 +1,270 @@
+use std::{
+    pin::Pin,
+    cmp::{Ordering as CmpOrdering, Reverse},
+    collections::{BinaryHeap, VecDeque},
+    sync::{Arc, atomic::{AtomicU64, Ordering}},
+    time::Duration,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::{Stream, StreamExt, stream, future::{self, Either}};
+use futures_timer::Delay;
+
+use crate::{
+    error::Result,
+    envelope::Envelope,
+    consumer::Consumer,
+    clock::{Clock, SystemClock},
+};
+
+
+/// What a [`ReorderingConsumer`] does with events arriving behind the watermark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LatePolicy {
+    /// Ack the late event and drop it.
+    #[default]
+    Drop,
+    /// Nack the late event, leaving it to the backend's redelivery or dead-lettering.
+    Nack,
+}
+
+
+struct Buffered {
+    time: DateTime<Utc>,
+    seq: u64,
+    envelope: Envelope,
+}
+
+impl PartialEq for Buffered {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for Buffered {}
+
+impl PartialOrd for Buffered {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Buffered {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        (self.time, self.seq).cmp(&(other.time, other.seq))
+    }
+}
+
+
+struct ReorderState {
+    envelopes: Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>,
+    buffer: BinaryHeap<Reverse<Buffered>>,
+    ready: VecDeque<Envelope>,
+    watermark: Option<DateTime<Utc>>,
+    latest: Option<DateTime<Utc>>,
+    seq: u64,
+    done: bool,
+}
+
+impl ReorderState {
+    fn release(&mut self, up_to: Option<DateTime<Utc>>) {
+        while self.buffer
+            .peek()
+            .is_some_and(|Reverse(b)| up_to.is_none_or(|t| b.time <= t))
+        {
+            if let Some(Reverse(buffered)) = self.buffer.pop() {
+                self.ready.push_back(buffered.envelope);
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        self.release(None);
+
+        if self.latest > self.watermark {
+            self.watermark = self.latest;
+        }
+    }
+}
+
+/// A consumer that buffers the envelopes of an inner consumer and emits them sorted by
+/// event time.
+///
+/// The watermark trails the latest event time seen by the allowed lateness. Buffered
+/// envelopes are emitted once the watermark passes their event time, and envelopes
+/// arriving with an event time behind the watermark are handled by the [`LatePolicy`].
+/// Events without a time are stamped with their arrival time.
+pub struct ReorderingConsumer<C: Consumer> {
+    inner: C,
+    lateness: Duration,
+    late_policy: LatePolicy,
+    late: Arc<AtomicU64>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<C: Consumer> ReorderingConsumer<C> {
+    /// Create a new [`ReorderingConsumer`] wrapping the given consumer.
+    ///
+    /// # Arguments
+    /// * `inner` - The consumer to reorder envelopes from.
+    /// * `lateness` - How far behind the latest event time an event may arrive.
+    ///
+    /// # Returns
+    /// The new [`ReorderingConsumer`].
+    pub fn new(inner: C, lateness: Duration) -> Self {
+        Self {
+            inner,
+            lateness,
+            late_policy: LatePolicy::default(),
+            late: Arc::new(AtomicU64::new(0)),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Set what is done with events arriving behind the watermark.
+    ///
+    /// # Arguments
+    /// * `policy` - The policy applied to late events.
+    ///
+    /// # Returns
+    /// The consumer with the policy set.
+    pub fn with_late_policy(mut self, policy: LatePolicy) -> Self {
+        self.late_policy = policy;
+        self
+    }
+
+    /// Set the clock used as the time of events without one.
+    ///
+    /// # Arguments
+    /// * `clock` - The clock to read the time from.
+    ///
+    /// # Returns
+    /// The consumer with the clock set.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns the number of late events handled by the late policy so far.
+    pub fn late(&self) -> u64 {
+        self.late.load(Ordering::Relaxed)
+    }
+
+    /// Returns a reference to the inner consumer.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<C: Consumer> Consumer for ReorderingConsumer<C> {
+    /// Stream envelopes sorted by event time.
+    ///
+    /// When no envelope arrives for the allowed lateness, or the inner stream ends, the
+    /// buffered envelopes are flushed in order and the watermark moves up to the latest
+    /// event time seen. Errors of the inner stream are yielded as they occur.
+    ///
+    /// # Returns
+    /// A result containing a stream of envelopes or an error.
+    async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
+        let lateness = self.lateness;
+        let lateness_delta = TimeDelta::from_std(lateness).unwrap_or(TimeDelta::MAX);
+        let late_policy = self.late_policy;
+        let late = self.late.clone();
+        let clock = self.clock.clone();
+        let state = ReorderState {
+            envelopes: self.inner.stream_events().await?,
+            buffer: BinaryHeap::new(),
+            ready: VecDeque::new(),
+            watermark: None,
+            latest: None,
+            seq: 0,
+            done: false,
+        };
+
+        Ok(Box::pin(stream::unfold(state, move |mut state| {
+            let late = late.clone();
+            let clock = clock.clone();
+
+            async move {
+                loop {
+                    if let Some(envelope) = state.ready.pop_front() {
+                        return Some((Ok(envelope), state));
+                    }
+
+                    if state.done {
+                        if state.buffer.is_empty() {
+                            return None;
+                        }
+
+                        state.flush();
+                        continue;
+                    }
+
+                    let next = if state.buffer.is_empty() {
+                        state.envelopes.next().await
+                    } else {
+                        match future::select(state.envelopes.next(), Delay::new(lateness)).await {
+                            Either::Left((next, _)) => next,
+                            Either::Right(_) => {
+                                state.flush();
+                                continue;
+                            }
+                        }
+                    };
+
+                    match next {
+                        Some(Ok(envelope)) => {
+                            let time = envelope
+                                .event()
+                                .time()
+                                .copied()
+                                .unwrap_or_else(|| clock.now());
+
+                            if state.watermark.is_some_and(|w| time < w) {
+                                late.fetch_add(1, Ordering::Relaxed);
+
+                                match late_policy {
+                                    LatePolicy::Drop => envelope.ack().await,
+                                    LatePolicy::Nack => envelope.nack().await,
+                                }
+
+                                continue;
+                            }
+
+                            let watermark = time
+                                .checked_sub_signed(lateness_delta)
+                                .unwrap_or(DateTime::<Utc>::MIN_UTC);
+
+                            if state.watermark.is_none_or(|w| watermark > w) {
+                                state.watermark = Some(watermark);
+                            }
+
+                            if state.latest.is_none_or(|l| time > l) {
+                                state.latest = Some(time);
+                            }
+
+                            state.buffer.push(Reverse(Buffered { time, seq: state.seq, envelope }));
+                            state.seq += 1;
+                            state.release(state.watermark);
+                        }
+                        Some(Err(e)) => return Some((Err(e), state)),
+                        None => state.done = true,
+                    }
+                }
+            }
+        })))
+    }
+
+    async fn pause(&self) -> Result<()> {
+        self.inner.pause().await
+    }
+
+    async fn resume(&self) -> Result<()> {
+        self.inner.resume().await
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+use futures::StreamExt;
+use futures_timeout::TimeoutExt;
+
+use crate::{
+    error::Result,
+    event::Event,
+    envelope::DELIVERY_COUNT_EXTENSION,
+    broker::Broker,
+    publisher::{Publisher, PublisherOptions},
+    consumer::{Consumer, ConsumerOptions},
+};
+
+
+/// Replay events from one channel, typically a dead-letter channel, to another.
+///
+/// Each event accepted by the filter has its delivery count extension stripped, is
+/// republished to the target channel and then acked. Events rejected by the filter are
+/// acked and skipped, so they are neither redelivered to the replay nor nacked to a
+/// backend that drops nacked events. On queue backends such as SQS, acking removes them
+/// from the source channel. The replay finishes once no event arrives for `idle_timeout`.
+///
+/// The source channel is read from its start where the backend allows it: the consumer
+/// sets [`ConsumerOptions::replay_from_start`], which the in-memory broker honours when it
+/// retains history, and the Kafka `auto.offset.reset` setting to `earliest`, so the
+/// `{from}.replay` group starts from the oldest retained record the first time it runs.
+/// Later runs resume from the group's committed offsets.
+///
+/// # Arguments
+/// * `broker` - The broker to consume and publish with.
+/// * `from` - The channel to replay events from.
+/// * `to` - The channel to republish events to.
+/// * `filter` - A predicate deciding which events are replayed.
+/// * `idle_timeout` - How long to wait for the next event before finishing.
+///
+/// # Returns
+/// A result containing the number of replayed events or an error.
+pub async fn replay<B, F>(broker: &B, from: &str, to: &str, filter: F, idle_timeout: Duration) -> Result<usize>
+where
+    B: Broker,
+    F: Fn(&Event) -> bool,
+{
+    let publisher = broker
+        .publisher(PublisherOptions::builder().channel(to).try_build()?)
+        .await?;
+    let consumer = broker
+        .consumer(
+            ConsumerOptions::builder()
+                .channel(from)
+                .consumer_tag(format!("{}.replay", from))
+                .replay_from_start(true)
+                .extra("auto.offset.reset", "earliest")
+                .try_build()?
+        )
+        .await?;
+
+    let mut envelopes = consumer
+        .stream_events()
+        .await?
+        .timeout(idle_timeout);
+    let mut replayed = 0;
+
+    while let Some(Ok(envelope)) = envelopes.next().await {
+        let envelope = envelope?;
+        let mut event = envelope.event().clone();
+
+        if !filter(&event) {
+            envelope.ack().await;
+            continue;
+        }
+
+        event.0.remove_extension(DELIVERY_COUNT_EXTENSION);
+
+        if let Err(e) = publisher.publish_event(event).await {
+            envelope.nack().await;
+            return Err(e);
+        }
+
+        envelope.ack().await;
+        replayed += 1;
+    }
+
+    Ok(replayed)
+}
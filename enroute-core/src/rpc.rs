@@ -0,0 +1,252 @@
+use std::{collections::HashMap, future::Future, pin::{Pin, pin}, sync::{Arc, Mutex as StdMutex}, time::Duration};
+use futures::{Stream, StreamExt, channel::oneshot, future::{self, Either}};
+use futures_timeout::TimeoutExt;
+use mea::mutex::Mutex;
+
+use crate::{
+    error::{Error, Result},
+    event::{Event, CORRELATION_ID_EXTENSION, extension_value_to_string},
+    envelope::Envelope,
+    publisher::Publisher,
+    consumer::Consumer,
+};
+
+
+/// The extension naming the channel a request expects its reply on.
+pub const REPLY_TO_EXTENSION: &str = "replyto";
+
+/// The default time [`RpcClient::request`] waits for a reply.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+type PendingRequests = Arc<StdMutex<HashMap<String, oneshot::Sender<Event>>>>;
+
+/// The reader of the reply stream, running until the stream ends.
+type ReplyReader = Pin<Box<dyn Future<Output = Error> + Send>>;
+
+/// Returns the correlation id of an event, defaulting to its id.
+fn correlation_id(event: &Event) -> String {
+    event
+        .correlation_id()
+        .unwrap_or_else(|| event.id().to_string())
+}
+
+/// A client making requests over events and awaiting their replies.
+///
+/// Requests are published with a [`REPLY_TO_EXTENSION`] naming the reply channel and a
+/// [`CORRELATION_ID_EXTENSION`], which the reply must carry back (see [`RpcServer`]).
+/// Replies are read from the client's consumer of the reply channel by a single reader
+/// shared by concurrent requests, which hands each reply to the request matching its
+/// correlation id before acking it. The reader is driven by whichever requests are
+/// waiting, and outlives any of them, so a request timing out never loses the reply of
+/// another. Replies matching no pending request are acked and dropped.
+pub struct RpcClient<P: Publisher, C: Consumer> {
+    publisher: P,
+    consumer: C,
+    reply_channel: String,
+    timeout: Duration,
+    reader: Mutex<Option<ReplyReader>>,
+    pending: PendingRequests,
+}
+
+impl<P: Publisher, C: Consumer> RpcClient<P, C> {
+    /// Create a new [`RpcClient`].
+    ///
+    /// # Arguments
+    /// * `publisher` - The publisher of the request channel.
+    /// * `consumer` - The consumer of the reply channel, used by this client only.
+    /// * `reply_channel` - The name of the reply channel.
+    ///
+    /// # Returns
+    /// The new [`RpcClient`].
+    pub fn new(publisher: P, consumer: C, reply_channel: impl Into<String>) -> Self {
+        Self {
+            publisher,
+            consumer,
+            reply_channel: reply_channel.into(),
+            timeout: DEFAULT_TIMEOUT,
+            reader: Mutex::new(None),
+            pending: Arc::new(StdMutex::new(HashMap::new())),
+        }
+    }
+
+    /// Set how long a request waits for its reply, 30 seconds by default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the name of the reply channel.
+    pub fn reply_channel(&self) -> &str {
+        &self.reply_channel
+    }
+
+    /// Publish a request and wait for its reply.
+    ///
+    /// The request keeps its correlation id if it has one, and is otherwise correlated by
+    /// its id.
+    ///
+    /// # Arguments
+    /// * `request` - The request event.
+    ///
+    /// # Returns
+    /// A result containing the reply event, or an [`Error::Timeout`] if no reply arrives
+    /// in time.
+    pub async fn request(&self, mut request: Event) -> Result<Event> {
+        let correlation_id = correlation_id(&request);
+        let (sender, receiver) = oneshot::channel();
+
+        request.set_extension(CORRELATION_ID_EXTENSION, correlation_id.as_str());
+        request.set_extension(REPLY_TO_EXTENSION, self.reply_channel.as_str());
+
+        // Register before publishing, so a quick reply finds its request.
+        self.pending_requests().insert(correlation_id.clone(), sender);
+
+        let reply = match self.publisher.publish_event(request).await {
+            Ok(()) => self.await_reply(receiver)
+                .timeout(self.timeout)
+                .await
+                .unwrap_or_else(|_| Err(Error::Timeout(format!("no reply to request {}", correlation_id)))),
+            Err(e) => Err(e),
+        };
+
+        self.pending_requests().remove(&correlation_id);
+        reply
+    }
+
+    fn pending_requests(&self) -> std::sync::MutexGuard<'_, HashMap<String, oneshot::Sender<Event>>> {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn await_reply(&self, mut receiver: oneshot::Receiver<Event>) -> Result<Event> {
+        match future::select(&mut receiver, pin!(self.drive_reader())).await {
+            Either::Left((reply, _)) => reply.map_err(|_| Error::Consumer("reply dropped".to_string())),
+            Either::Right((error, _)) => Err(error),
+        }
+    }
+
+    /// Drive the shared reply reader until the reply stream ends, starting it if needed.
+    /// Dropping the returned future only releases the reader for other requests.
+    async fn drive_reader(&self) -> Error {
+        let mut reader = self.reader.lock().await;
+        let running = match reader.as_mut() {
+            Some(running) => running,
+            None => match self.consumer.stream_events().await {
+                Ok(replies) => reader.insert(Box::pin(read_replies(replies, self.pending.clone()))),
+                Err(e) => return e,
+            },
+        };
+        let error = running.await;
+
+        // The reader has completed, so the next request starts a new one.
+        reader.take();
+        error
+    }
+}
+
+/// Read replies from the stream, handing each to its pending request before acking it,
+/// until the stream ends.
+async fn read_replies(
+    mut replies: Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>,
+    pending: PendingRequests,
+) -> Error {
+    while let Some(envelope) = replies.next().await {
+        let Ok(envelope) = envelope else {
+            continue;
+        };
+        let reply = envelope.event().clone();
+        let sender = reply
+            .correlation_id()
+            .and_then(|id| pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&id));
+
+        if let Some(sender) = sender {
+            let _ = sender.send(reply);
+        }
+
+        envelope.ack().await;
+    }
+
+    Error::Consumer("reply stream ended".to_string())
+}
+
+/// A server consuming requests made by [`RpcClient`]s and publishing their replies.
+pub struct RpcServer<C: Consumer, P: Publisher> {
+    consumer: C,
+    publisher: P,
+}
+
+impl<C: Consumer, P: Publisher> RpcServer<C, P> {
+    /// Create a new [`RpcServer`].
+    ///
+    /// # Arguments
+    /// * `consumer` - The consumer of the request channel.
+    /// * `publisher` - The publisher used to publish replies to the channel each request
+    ///   names.
+    ///
+    /// # Returns
+    /// The new [`RpcServer`].
+    pub fn new(consumer: C, publisher: P) -> Self {
+        Self { consumer, publisher }
+    }
+
+    /// Publish the reply to a request on the channel it names, with the request's
+    /// correlation id.
+    ///
+    /// Requests without a [`REPLY_TO_EXTENSION`] expect no reply, so nothing is published.
+    ///
+    /// # Arguments
+    /// * `request` - The request being replied to.
+    /// * `reply` - The reply event.
+    ///
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn reply(&self, request: &Event, mut reply: Event) -> Result<()> {
+        let Some(reply_to) = request.0
+            .extension(REPLY_TO_EXTENSION)
+            .map(extension_value_to_string)
+        else {
+            return Ok(());
+        };
+
+        reply.set_extension(CORRELATION_ID_EXTENSION, correlation_id(request).as_str());
+
+        self.publisher
+            .publish_event_to(&reply_to, reply)
+            .await
+    }
+
+    /// Handle requests until the shutdown future completes, publishing the reply the
+    /// handler returns for each.
+    ///
+    /// Requests are acked once replied to, and nacked if the handler or publishing the
+    /// reply fails.
+    ///
+    /// # Arguments
+    /// * `shutdown` - A future that completes when the server should stop.
+    /// * `handler` - The function computing the reply to a request.
+    ///
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn run_until_shutdown<S, F, Fut>(&self, shutdown: S, mut handler: F) -> Result<()>
+    where
+        S: Future<Output = ()> + Send,
+        F: FnMut(Event) -> Fut + Send,
+        Fut: Future<Output = Result<Event>> + Send,
+    {
+        self.consumer
+            .run_until_shutdown(shutdown, |request| {
+                let reply = handler(request.clone());
+
+                async move {
+                    self.reply(&request, reply.await?).await
+                }
+            })
+            .await
+    }
+
+    /// Returns a reference to the consumer of the request channel.
+    pub fn consumer(&self) -> &C {
+        &self.consumer
+    }
+}
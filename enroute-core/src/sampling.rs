@@ -0,0 +1,136 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, atomic::{AtomicU64, Ordering}},
+};
+use async_trait::async_trait;
+
+use crate::{error::Result, event::Event, publisher::Publisher};
+
+
+/// The rate at which a [`SamplingPublisher`] lets events through.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleRate {
+    /// Publish each event with the given probability, clamped to `0.0..=1.0`.
+    Probability(f64),
+    /// Publish every Nth event of a given type, starting with the first.
+    EveryNth(u64),
+}
+
+impl SampleRate {
+    fn should_publish(&self, count: u64) -> bool {
+        match *self {
+            SampleRate::Probability(p) => rand::random::<f64>() < p.clamp(0.0, 1.0),
+            SampleRate::EveryNth(n) => n != 0 && count % n == 0,
+        }
+    }
+}
+
+
+/// A publisher that only publishes a sampled fraction of events, dropping the rest.
+///
+/// The rate can be overridden per event type, in which case events of that type are
+/// sampled with their own rate instead of the default.
+pub struct SamplingPublisher<P: Publisher> {
+    inner: P,
+    default_rate: SampleRate,
+    type_rates: HashMap<String, SampleRate>,
+    counts: Mutex<HashMap<String, u64>>,
+    dropped: AtomicU64,
+}
+
+impl<P: Publisher> SamplingPublisher<P> {
+    /// Create a new [`SamplingPublisher`] wrapping the given publisher.
+    ///
+    /// # Arguments
+    /// * `inner` - The publisher to forward sampled events to.
+    /// * `rate` - The default sample rate applied to all event types.
+    ///
+    /// # Returns
+    /// The new [`SamplingPublisher`].
+    pub fn new(inner: P, rate: SampleRate) -> Self {
+        Self {
+            inner,
+            default_rate: rate,
+            type_rates: HashMap::new(),
+            counts: Mutex::new(HashMap::new()),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Set the sample rate for a specific event type.
+    ///
+    /// # Arguments
+    /// * `event_type` - The event type the rate applies to.
+    /// * `rate` - The sample rate for that event type.
+    ///
+    /// # Returns
+    /// The publisher with the rate set.
+    pub fn with_type_rate(mut self, event_type: impl Into<String>, rate: SampleRate) -> Self {
+        self.type_rates.insert(event_type.into(), rate);
+        self
+    }
+
+    /// Returns the number of events dropped by sampling so far.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Returns a reference to the inner publisher.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Consume the sampling publisher and return the inner publisher.
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+
+    fn sample(&self, event: &Event) -> bool {
+        let rate = self.type_rates
+            .get(event.type_())
+            .unwrap_or(&self.default_rate);
+
+        let count = {
+            let mut counts = self.counts
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            let count = counts
+                .entry(event.type_().to_string())
+                .or_insert(0);
+            let current = *count;
+            *count = count.wrapping_add(1);
+            current
+        };
+
+        rate.should_publish(count)
+    }
+}
+
+#[async_trait]
+impl<P: Publisher> Publisher for SamplingPublisher<P> {
+    async fn publish_event(&self, event: Event) -> Result<()> {
+        if !self.sample(&event) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+
+        self.inner.publish_event(event).await
+    }
+
+    async fn publish_event_to(&self, channel: &str, event: Event) -> Result<()> {
+        if !self.sample(&event) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return Ok(());
+        }
+
+        self.inner.publish_event_to(channel, event).await
+    }
+
+    fn channel(&self) -> &str {
+        self.inner.channel()
+    }
+
+    fn strict_channel_check(&self) -> bool {
+        self.inner.strict_channel_check()
+    }
+}
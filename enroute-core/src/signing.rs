@@ -0,0 +1,342 @@
+use std::{pin::Pin, sync::Arc};
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use ed25519_dalek::{Signer, Verifier};
+
+use crate::{
+    error::{Error, Result},
+    event::{Event, extension_value_to_string},
+    envelope::Envelope,
+    publisher::Publisher,
+    consumer::{Consumer, ConsumerEvent},
+};
+
+
+/// The extension carrying the base64-encoded signature of an event.
+pub const SIGNATURE_EXTENSION: &str = "signature";
+
+/// Returns the bytes an event's signature is computed over.
+///
+/// They cover the id, source, type, subject, content type and data of the event, each
+/// prefixed with its length, and the data as returned by [`Event::data_as_bytes`].
+/// Extensions and the time are not covered, as brokers add extensions and may change the
+/// precision of the time in transit. A missing content type is covered as
+/// `application/json`, which consumers assume for events received without one.
+///
+/// # Arguments
+/// * `event` - The event to return the canonical bytes of.
+///
+/// # Returns
+/// A result containing the canonical bytes or an error.
+pub fn canonical_bytes(event: &Event) -> Result<Vec<u8>> {
+    let data = match event.data_as_bytes() {
+        Ok(data) => data,
+        Err(Error::MissingEventData) => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    let fields: [&[u8]; 6] = [
+        event.id().as_bytes(),
+        event.source().as_bytes(),
+        event.type_().as_bytes(),
+        event.subject().unwrap_or_default().as_bytes(),
+        event.datacontenttype().unwrap_or("application/json").as_bytes(),
+        &data,
+    ];
+    let mut bytes = Vec::with_capacity(fields.iter().map(|field| field.len() + 8).sum());
+
+    for field in fields {
+        bytes.extend_from_slice(&(field.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(field);
+    }
+
+    Ok(bytes)
+}
+
+
+/// A key signing events.
+#[derive(Clone)]
+pub enum SigningKey {
+    /// An HMAC-SHA256 shared secret.
+    HmacSha256(Vec<u8>),
+    /// An Ed25519 private key.
+    Ed25519(ed25519_dalek::SigningKey),
+}
+
+impl SigningKey {
+    /// Create an HMAC-SHA256 key from a shared secret.
+    pub fn hmac_sha256(secret: impl Into<Vec<u8>>) -> Self {
+        Self::HmacSha256(secret.into())
+    }
+
+    /// Create an Ed25519 key from the bytes of a private key.
+    pub fn ed25519(secret_key: &[u8; 32]) -> Self {
+        Self::Ed25519(ed25519_dalek::SigningKey::from_bytes(secret_key))
+    }
+
+    /// Returns the key verifying the signatures made with this key: the shared secret for
+    /// HMAC-SHA256, and the public key for Ed25519.
+    pub fn verifying_key(&self) -> VerifyingKey {
+        match self {
+            Self::HmacSha256(secret) => VerifyingKey::HmacSha256(secret.clone()),
+            Self::Ed25519(key) => VerifyingKey::Ed25519(key.verifying_key()),
+        }
+    }
+
+    /// Sign an event, setting its [`SIGNATURE_EXTENSION`].
+    ///
+    /// # Arguments
+    /// * `event` - The event to sign.
+    ///
+    /// # Returns
+    /// A result indicating success or failure.
+    pub fn sign(&self, event: &mut Event) -> Result<()> {
+        let bytes = canonical_bytes(event)?;
+        let signature = match self {
+            Self::HmacSha256(secret) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+                    .map_err(|e| Error::Publisher(e.to_string()))?;
+
+                mac.update(&bytes);
+                mac.finalize().into_bytes().to_vec()
+            }
+            Self::Ed25519(key) => key.sign(&bytes).to_bytes().to_vec(),
+        };
+
+        event.set_extension(SIGNATURE_EXTENSION, BASE64_STANDARD.encode(signature));
+        Ok(())
+    }
+}
+
+/// A key verifying the signatures of events.
+#[derive(Clone)]
+pub enum VerifyingKey {
+    /// An HMAC-SHA256 shared secret.
+    HmacSha256(Vec<u8>),
+    /// An Ed25519 public key.
+    Ed25519(ed25519_dalek::VerifyingKey),
+}
+
+impl VerifyingKey {
+    /// Create an HMAC-SHA256 key from a shared secret.
+    pub fn hmac_sha256(secret: impl Into<Vec<u8>>) -> Self {
+        Self::HmacSha256(secret.into())
+    }
+
+    /// Create an Ed25519 key from the bytes of a public key.
+    ///
+    /// # Returns
+    /// A result containing the key, or an [`Error::Builder`] if the bytes are not a valid
+    /// public key.
+    pub fn ed25519(public_key: &[u8; 32]) -> Result<Self> {
+        ed25519_dalek::VerifyingKey::from_bytes(public_key)
+            .map(Self::Ed25519)
+            .map_err(|e| Error::Builder(e.to_string()))
+    }
+
+    /// Verify the signature of an event.
+    ///
+    /// # Arguments
+    /// * `event` - The event to verify.
+    ///
+    /// # Returns
+    /// A result indicating success, or an [`Error::Consumer`] if the event is not signed
+    /// or its signature does not match.
+    pub fn verify(&self, event: &Event) -> Result<()> {
+        let invalid = || Error::Consumer(format!("invalid signature on event {}", event.id()));
+        let signature = event.0
+            .extension(SIGNATURE_EXTENSION)
+            .map(extension_value_to_string)
+            .ok_or_else(|| Error::Consumer(format!("missing signature on event {}", event.id())))?;
+        let signature = BASE64_STANDARD
+            .decode(signature)
+            .map_err(|_| invalid())?;
+        let bytes = canonical_bytes(event)?;
+
+        match self {
+            Self::HmacSha256(secret) => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+                    .map_err(|e| Error::Consumer(e.to_string()))?;
+
+                mac.update(&bytes);
+                mac.verify_slice(&signature).map_err(|_| invalid())
+            }
+            Self::Ed25519(key) => {
+                let signature = ed25519_dalek::Signature::from_slice(&signature)
+                    .map_err(|_| invalid())?;
+
+                key.verify(&bytes, &signature).map_err(|_| invalid())
+            }
+        }
+    }
+}
+
+
+/// A publisher signing events before publishing them with the inner publisher.
+pub struct SigningPublisher<P: Publisher> {
+    inner: P,
+    key: SigningKey,
+}
+
+impl<P: Publisher> SigningPublisher<P> {
+    /// Create a new [`SigningPublisher`].
+    ///
+    /// # Arguments
+    /// * `inner` - The publisher to publish signed events with.
+    /// * `key` - The key to sign events with.
+    ///
+    /// # Returns
+    /// The new [`SigningPublisher`].
+    pub fn new(inner: P, key: SigningKey) -> Self {
+        Self { inner, key }
+    }
+
+    /// Returns a reference to the inner publisher.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<P: Publisher> Publisher for SigningPublisher<P> {
+    async fn publish_event(&self, mut event: Event) -> Result<()> {
+        self.key.sign(&mut event)?;
+        self.inner.publish_event(event).await
+    }
+
+    async fn publish_event_to(&self, channel: &str, mut event: Event) -> Result<()> {
+        self.key.sign(&mut event)?;
+        self.inner.publish_event_to(channel, event).await
+    }
+
+    fn channel(&self) -> &str {
+        self.inner.channel()
+    }
+
+    fn strict_channel_check(&self) -> bool {
+        self.inner.strict_channel_check()
+    }
+}
+
+
+/// How a [`VerifyingConsumer`] settles the envelopes whose signature fails verification.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidSignaturePolicy {
+    /// Ack the envelope, dropping the event.
+    #[default]
+    Drop,
+    /// Nack the envelope, e.g. to route it to a dead letter queue.
+    Nack,
+}
+
+/// A consumer verifying the signatures of events, skipping the ones that are not signed
+/// or whose signature does not match.
+pub struct VerifyingConsumer<C: Consumer> {
+    inner: C,
+    key: Arc<VerifyingKey>,
+    policy: InvalidSignaturePolicy,
+}
+
+impl<C: Consumer> VerifyingConsumer<C> {
+    /// Create a new [`VerifyingConsumer`].
+    ///
+    /// # Arguments
+    /// * `inner` - The consumer to verify events of.
+    /// * `key` - The key to verify signatures with.
+    ///
+    /// # Returns
+    /// The new [`VerifyingConsumer`].
+    pub fn new(inner: C, key: VerifyingKey) -> Self {
+        Self {
+            inner,
+            key: Arc::new(key),
+            policy: InvalidSignaturePolicy::default(),
+        }
+    }
+
+    /// Set how envelopes failing verification are settled, dropped by default.
+    pub fn with_policy(mut self, policy: InvalidSignaturePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns a reference to the inner consumer.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+/// Returns the envelope if its event is verified, settling it otherwise.
+async fn verified(envelope: Envelope, key: &VerifyingKey, policy: InvalidSignaturePolicy) -> Option<Envelope> {
+    if key.verify(envelope.event()).is_ok() {
+        return Some(envelope);
+    }
+
+    match policy {
+        InvalidSignaturePolicy::Drop => envelope.ack().await,
+        InvalidSignaturePolicy::Nack => envelope.nack().await,
+    }
+
+    None
+}
+
+#[async_trait]
+impl<C: Consumer> Consumer for VerifyingConsumer<C> {
+    async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
+        let key = self.key.clone();
+        let policy = self.policy;
+
+        Ok(Box::pin(
+            self.inner
+                .stream_events()
+                .await?
+                .filter_map(move |envelope| {
+                    let key = key.clone();
+
+                    async move {
+                        match envelope {
+                            Ok(envelope) => verified(envelope, &key, policy).await.map(Ok),
+                            Err(e) => Some(Err(e)),
+                        }
+                    }
+                })
+        ))
+    }
+
+    async fn pause(&self) -> Result<()> {
+        self.inner.pause().await
+    }
+
+    async fn resume(&self) -> Result<()> {
+        self.inner.resume().await
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    async fn stream_with_control(&self) -> Result<Pin<Box<dyn Stream<Item = Result<ConsumerEvent>> + Send>>> {
+        let key = self.key.clone();
+        let policy = self.policy;
+
+        Ok(Box::pin(
+            self.inner
+                .stream_with_control()
+                .await?
+                .filter_map(move |event| {
+                    let key = key.clone();
+
+                    async move {
+                        match event {
+                            Ok(ConsumerEvent::Message(envelope)) => verified(envelope, &key, policy)
+                                .await
+                                .map(|envelope| Ok(ConsumerEvent::Message(envelope))),
+                            event => Some(event),
+                        }
+                    }
+                })
+        ))
+    }
+}
@@ -0,0 +1,91 @@
+use std::future::Future;
+
+use crate::{
+    error::Result,
+    event::Event,
+    publisher::Publisher,
+    consumer::Consumer,
+};
+
+
+/// A consumer republishing the events of one channel to channels computed from each event,
+/// e.g. to split a firehose into per-tenant channels.
+///
+/// Each source envelope is acked only once its event is republished, and nacked if
+/// republishing fails, so events are routed at least once.
+pub struct SplitterConsumer<C, P, F>
+where
+    C: Consumer,
+    P: Publisher,
+    F: Fn(&Event) -> String + Send + Sync,
+{
+    consumer: C,
+    publisher: P,
+    route: F,
+}
+
+impl<C, P, F> SplitterConsumer<C, P, F>
+where
+    C: Consumer,
+    P: Publisher,
+    F: Fn(&Event) -> String + Send + Sync,
+{
+    /// Create a new [`SplitterConsumer`].
+    ///
+    /// # Arguments
+    /// * `consumer` - The consumer of the source channel.
+    /// * `publisher` - The publisher to republish events with, which must support
+    ///   [`Publisher::publish_event_to`].
+    /// * `route` - The function computing the channel to republish an event to.
+    ///
+    /// # Returns
+    /// The new [`SplitterConsumer`].
+    pub fn new(consumer: C, publisher: P, route: F) -> Self {
+        Self {
+            consumer,
+            publisher,
+            route,
+        }
+    }
+
+    /// Republish an event to the channel computed for it.
+    ///
+    /// # Arguments
+    /// * `event` - The event to republish.
+    ///
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn split(&self, event: Event) -> Result<()> {
+        let channel = (self.route)(&event);
+
+        self.publisher
+            .publish_event_to(&channel, event)
+            .await
+    }
+
+    /// Republish events until the shutdown future completes.
+    ///
+    /// # Arguments
+    /// * `shutdown` - A future that completes when the splitter should stop.
+    ///
+    /// # Returns
+    /// A result indicating a graceful shutdown, or the first error of the event stream.
+    pub async fn run_until_shutdown<S>(&self, shutdown: S) -> Result<()>
+    where
+        S: Future<Output = ()> + Send,
+    {
+        self.consumer
+            .run_until_shutdown(shutdown, |event| self.split(event))
+            .await
+    }
+
+    /// Returns a reference to the consumer of the source channel.
+    pub fn consumer(&self) -> &C {
+        &self.consumer
+    }
+
+    /// Returns a reference to the publisher events are republished with.
+    pub fn publisher(&self) -> &P {
+        &self.publisher
+    }
+}
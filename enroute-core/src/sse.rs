@@ -0,0 +1,119 @@
+use std::{pin::Pin, sync::Arc};
+use futures::{Stream, StreamExt, stream};
+use tokio::runtime::Handle;
+
+use crate::{
+    error::Result,
+    envelope::Envelope,
+    event::Event,
+    consumer::Consumer,
+};
+
+
+/// Format an event as a Server-Sent Events message, carrying the event in the
+/// structured CloudEvents JSON format: `data: <cloudevents-json>\n\n`.
+///
+/// # Arguments
+/// * `event` - The event to format.
+///
+/// # Returns
+/// A result containing the message bytes or an error.
+pub fn format_sse(event: &Event) -> Result<Vec<u8>> {
+    Ok(format!("data: {}\n\n", event.to_json()?).into_bytes())
+}
+
+/// The state of an SSE byte stream, settling the unflushed envelope and closing the
+/// consumer when the stream is dropped, e.g. because the client disconnected.
+struct SseState {
+    envelopes: Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>,
+    flushed: Option<Envelope>,
+    consumer: Option<Arc<dyn Consumer>>,
+}
+
+impl Drop for SseState {
+    fn drop(&mut self) {
+        let flushed = self.flushed.take();
+        let consumer = self.consumer.take();
+
+        if flushed.is_none() && consumer.is_none() {
+            return;
+        }
+
+        // Settling and closing are async, so they run on the runtime the stream was
+        // polled on, after the envelope stream itself has been dropped.
+        if let Ok(handle) = Handle::try_current() {
+            handle.spawn(async move {
+                if let Some(envelope) = flushed {
+                    envelope.nack().await;
+                }
+
+                if let Some(consumer) = consumer {
+                    let _ = consumer.close().await;
+                }
+            });
+        }
+    }
+}
+
+fn sse_state_stream(state: SseState) -> Pin<Box<dyn Stream<Item = Result<Vec<u8>>> + Send>> {
+    Box::pin(stream::unfold(state, |mut state| async move {
+        if let Some(envelope) = state.flushed.take() {
+            envelope.ack().await;
+        }
+
+        match state.envelopes.next().await? {
+            Ok(envelope) => match format_sse(envelope.event()) {
+                Ok(message) => {
+                    state.flushed = Some(envelope);
+                    Some((Ok(message), state))
+                }
+                Err(e) => {
+                    envelope.nack().await;
+                    Some((Err(e), state))
+                }
+            },
+            Err(e) => Some((Err(e), state)),
+        }
+    }))
+}
+
+/// Turn a stream of envelopes into a Server-Sent Events byte stream.
+///
+/// Each envelope is acked once its message has been taken and the next one is
+/// requested, i.e. once the server has flushed it to the client. When the client
+/// disconnects and the byte stream is dropped, the envelope stream is dropped with it
+/// and the last envelope, whose message may not have reached the client, is nacked on
+/// the current Tokio runtime.
+///
+/// # Arguments
+/// * `envelopes` - The envelopes to stream, typically from [`Consumer::stream_events`].
+///
+/// # Returns
+/// A stream of SSE messages.
+pub fn sse_stream(
+    envelopes: Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>,
+) -> Pin<Box<dyn Stream<Item = Result<Vec<u8>>> + Send>> {
+    sse_state_stream(SseState {
+        envelopes,
+        flushed: None,
+        consumer: None,
+    })
+}
+
+/// Stream the events of a consumer as Server-Sent Events (see [`sse_stream`]).
+///
+/// The byte stream owns the consumer, and closes it with [`Consumer::close`] once the
+/// stream is dropped, e.g. when the client disconnects.
+///
+/// # Arguments
+/// * `consumer` - The consumer to stream events from.
+///
+/// # Returns
+/// A result containing a stream of SSE messages or an error.
+pub async fn stream_sse(consumer: Arc<dyn Consumer>) -> Result<Pin<Box<dyn Stream<Item = Result<Vec<u8>>> + Send>>> {
+    Ok(sse_state_stream(SseState {
+        envelopes: consumer.stream_events().await?,
+        flushed: None,
+        consumer: Some(consumer),
+    }))
+}
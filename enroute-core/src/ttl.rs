@@ -0,0 +1,128 @@
+use std::{pin::Pin, sync::{Arc, atomic::{AtomicU64, Ordering}}};
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+
+use crate::{
+    error::Result,
+    envelope::Envelope,
+    clock::{Clock, SystemClock},
+    consumer::{Consumer, ConsumerEvent},
+};
+
+
+/// A consumer dropping expired events before they reach the handler.
+///
+/// Envelopes whose event is expired by the time the stream yields it (see
+/// [`Event::is_expired`](crate::event::Event::is_expired)) are acked and skipped, so they
+/// are not redelivered. Events without an expiry time are passed through.
+pub struct TtlConsumer<C: Consumer> {
+    inner: C,
+    clock: Arc<dyn Clock>,
+    expired: Arc<AtomicU64>,
+}
+
+impl<C: Consumer> TtlConsumer<C> {
+    /// Create a new [`TtlConsumer`] wrapping the given consumer.
+    ///
+    /// # Arguments
+    /// * `inner` - The consumer to drop expired events of.
+    ///
+    /// # Returns
+    /// The new [`TtlConsumer`].
+    pub fn new(inner: C) -> Self {
+        Self {
+            inner,
+            clock: Arc::new(SystemClock),
+            expired: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Set the clock expiry times are compared against, the system clock by default.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns the number of expired events dropped so far.
+    pub fn expired_count(&self) -> u64 {
+        self.expired.load(Ordering::Relaxed)
+    }
+
+    /// Returns a reference to the inner consumer.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+/// Returns the envelope if its event is not expired, acking it otherwise.
+async fn unexpired(envelope: Envelope, clock: &dyn Clock, expired: &AtomicU64) -> Option<Envelope> {
+    if !envelope.event().is_expired_at(clock.now()) {
+        return Some(envelope);
+    }
+
+    expired.fetch_add(1, Ordering::Relaxed);
+    envelope.ack().await;
+
+    None
+}
+
+#[async_trait]
+impl<C: Consumer> Consumer for TtlConsumer<C> {
+    async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
+        let clock = self.clock.clone();
+        let expired = self.expired.clone();
+
+        Ok(Box::pin(
+            self.inner
+                .stream_events()
+                .await?
+                .filter_map(move |envelope| {
+                    let clock = clock.clone();
+                    let expired = expired.clone();
+
+                    async move {
+                        match envelope {
+                            Ok(envelope) => unexpired(envelope, clock.as_ref(), &expired).await.map(Ok),
+                            Err(e) => Some(Err(e)),
+                        }
+                    }
+                })
+        ))
+    }
+
+    async fn pause(&self) -> Result<()> {
+        self.inner.pause().await
+    }
+
+    async fn resume(&self) -> Result<()> {
+        self.inner.resume().await
+    }
+
+    async fn close(&self) -> Result<()> {
+        self.inner.close().await
+    }
+
+    async fn stream_with_control(&self) -> Result<Pin<Box<dyn Stream<Item = Result<ConsumerEvent>> + Send>>> {
+        let clock = self.clock.clone();
+        let expired = self.expired.clone();
+
+        Ok(Box::pin(
+            self.inner
+                .stream_with_control()
+                .await?
+                .filter_map(move |event| {
+                    let clock = clock.clone();
+                    let expired = expired.clone();
+
+                    async move {
+                        match event {
+                            Ok(ConsumerEvent::Message(envelope)) => unexpired(envelope, clock.as_ref(), &expired)
+                                .await
+                                .map(|envelope| Ok(ConsumerEvent::Message(envelope))),
+                            event => Some(event),
+                        }
+                    }
+                })
+        ))
+    }
+}
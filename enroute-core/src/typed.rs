@@ -0,0 +1,124 @@
+use std::{pin::Pin, marker::PhantomData};
+use futures::{Stream, StreamExt, future, channel::mpsc::unbounded};
+
+use crate::{
+    error::{Error, Result},
+    envelope::Envelope,
+    event::{Event, EventBuilder, EventData},
+    publisher::{Publisher, PublisherOptions},
+    broker::Broker,
+};
+
+
+/// A stream of envelopes paired with their deserialized event data.
+pub type TypedStream<E> = Pin<Box<dyn Stream<Item = Result<(E, Envelope)>> + Send>>;
+
+/// A stream of envelopes whose event data failed to deserialize.
+pub type FailureStream = Pin<Box<dyn Stream<Item = DeserializationFailure> + Send>>;
+
+
+/// An envelope whose event data failed to deserialize into the expected type.
+#[derive(Debug)]
+pub struct DeserializationFailure {
+    /// The envelope carrying the event, still to be acked or nacked.
+    pub envelope: Envelope,
+    /// The error raised while deserializing the event data.
+    pub error: Error,
+}
+
+
+/// Split a stream of envelopes into a stream of deserialized event data and a stream
+/// of deserialization failures.
+///
+/// Failures are only routed to the failure stream as the typed stream is polled, and
+/// the failure stream ends once the typed stream is dropped.
+///
+/// # Arguments
+/// * `envelopes` - The stream of envelopes to deserialize.
+///
+/// # Returns
+/// The typed stream and the failure stream.
+pub fn split_typed<E: EventData>(
+    envelopes: Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>,
+) -> (TypedStream<E>, FailureStream) {
+    let (failures_tx, failures_rx) = unbounded();
+
+    let typed = envelopes.filter_map(move |envelope| future::ready(
+        match envelope {
+            Ok(envelope) => match envelope.event().data::<E>() {
+                Ok(data) => Some(Ok((data, envelope))),
+                Err(error) => {
+                    let _ = failures_tx.unbounded_send(DeserializationFailure { envelope, error });
+                    None
+                }
+            },
+            Err(e) => Some(Err(e)),
+        }
+    ));
+
+    (Box::pin(typed), Box::pin(failures_rx))
+}
+
+
+/// A publisher bound to a single [`EventData`] type, publishing to the channel of that
+/// type.
+pub struct TypedPublisher<E: EventData, P: Publisher> {
+    inner: P,
+    _data: PhantomData<fn(E)>,
+}
+
+impl<E: EventData, P: Publisher> TypedPublisher<E, P> {
+    /// Create a new [`TypedPublisher`] wrapping the given publisher, which should
+    /// publish to [`EventData::channel_name`].
+    pub fn new(inner: P) -> Self {
+        Self { inner, _data: PhantomData }
+    }
+
+    /// Create a publisher for the channel of the event data type from a broker.
+    ///
+    /// # Arguments
+    /// * `broker` - The broker to create the publisher with.
+    ///
+    /// # Returns
+    /// A result containing the typed publisher or an error.
+    pub async fn from_broker<B>(broker: &B) -> Result<Self>
+    where
+        B: Broker<Publisher = P>,
+    {
+        broker
+            .publisher(PublisherOptions::builder().channel(E::channel_name()).build())
+            .await
+            .map(Self::new)
+    }
+
+    /// Publish an event carrying the given data.
+    ///
+    /// # Arguments
+    /// * `data` - The data of the event.
+    /// * `id` - The id of the event.
+    /// * `source` - The source of the event.
+    ///
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn publish(&self, data: E, id: impl Into<String>, source: impl Into<String>) -> Result<()> {
+        self.publish_with(Event::builder().id(id).source(source), data).await
+    }
+
+    /// Publish an event carrying the given data, built with the given builder to set
+    /// further attributes.
+    ///
+    /// # Arguments
+    /// * `builder` - The builder of the event, with its attributes set.
+    /// * `data` - The data of the event.
+    ///
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn publish_with(&self, builder: EventBuilder, data: E) -> Result<()> {
+        self.inner.publish_event(builder.build(data)?).await
+    }
+
+    /// Returns a reference to the inner publisher.
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use crate::{
+    error::{Error, Result},
+    event::{Event, EventData, extension_value_to_string},
+};
+
+
+/// The extension carrying the version of the schema an event's data follows.
+pub const DATA_SCHEMA_VERSION_EXTENSION: &str = "dataschemaversion";
+
+type Upgrader<L> = Box<dyn Fn(&Event) -> Result<L> + Send + Sync>;
+
+/// A deserializer of the data of events published with several versions of a schema,
+/// upgrading each to the latest version.
+///
+/// The version of an event is read from its [`DATA_SCHEMA_VERSION_EXTENSION`], so that
+/// consumers keep handling events of older versions during rolling deployments. Events
+/// without the extension are deserialized as the latest version, unless set otherwise
+/// with [`VersionedDeserializer::with_default_version`].
+pub struct VersionedDeserializer<L: EventData> {
+    latest_version: String,
+    default_version: Option<String>,
+    upgraders: HashMap<String, Upgrader<L>>,
+}
+
+impl<L: EventData> VersionedDeserializer<L> {
+    /// Create a new [`VersionedDeserializer`] for the latest version of a schema.
+    ///
+    /// # Arguments
+    /// * `latest_version` - The version of the latest data type.
+    ///
+    /// # Returns
+    /// The new [`VersionedDeserializer`].
+    pub fn new(latest_version: impl Into<String>) -> Self {
+        let latest_version = latest_version.into();
+        let mut upgraders: HashMap<String, Upgrader<L>> = HashMap::new();
+
+        upgraders.insert(latest_version.clone(), Box::new(|event| event.data::<L>()));
+
+        Self {
+            latest_version,
+            default_version: None,
+            upgraders,
+        }
+    }
+
+    /// Register the data type of an older version, with the function upgrading it to
+    /// the latest version, replacing any type previously registered for the version.
+    ///
+    /// # Arguments
+    /// * `version` - The version of the data type.
+    /// * `upgrade` - The function upgrading data of the version to the latest version.
+    ///
+    /// # Returns
+    /// The deserializer with the version registered.
+    pub fn register_version<V, F>(mut self, version: impl Into<String>, upgrade: F) -> Self
+    where
+        V: EventData,
+        F: Fn(V) -> Result<L> + Send + Sync + 'static,
+    {
+        self.upgraders.insert(
+            version.into(),
+            Box::new(move |event| upgrade(event.data::<V>()?)),
+        );
+        self
+    }
+
+    /// Set the version of events without a [`DATA_SCHEMA_VERSION_EXTENSION`], e.g. the
+    /// version published before versions were stamped on events.
+    pub fn with_default_version(mut self, version: impl Into<String>) -> Self {
+        self.default_version = Some(version.into());
+        self
+    }
+
+    /// Returns the version of the latest data type.
+    pub fn latest_version(&self) -> &str {
+        &self.latest_version
+    }
+
+    /// Returns whether a data type is registered for the given version.
+    pub fn is_registered(&self, version: &str) -> bool {
+        self.upgraders.contains_key(version)
+    }
+
+    /// Returns the version of an event's data, as this deserializer resolves it.
+    ///
+    /// # Arguments
+    /// * `event` - The event to return the version of.
+    ///
+    /// # Returns
+    /// The version named by the event, or the default version if it names none.
+    pub fn version_of(&self, event: &Event) -> String {
+        event.0
+            .extension(DATA_SCHEMA_VERSION_EXTENSION)
+            .map(extension_value_to_string)
+            .or_else(|| self.default_version.clone())
+            .unwrap_or_else(|| self.latest_version.clone())
+    }
+
+    /// Deserialize the event data into the type registered for its version, and upgrade
+    /// it to the latest version.
+    ///
+    /// # Arguments
+    /// * `event` - The event to deserialize the data of.
+    ///
+    /// # Returns
+    /// A result containing the data in its latest version, or an
+    /// [`Error::Deserialization`] if no type is registered for the event's version.
+    pub fn deserialize(&self, event: &Event) -> Result<L> {
+        let version = self.version_of(event);
+        let upgrader = self.upgraders
+            .get(&version)
+            .ok_or_else(|| Error::Deserialization(format!("Unregistered schema version: {}", version)))?;
+
+        upgrader(event)
+    }
+}
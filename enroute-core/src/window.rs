@@ -0,0 +1,192 @@
+use std::{pin::Pin, time::Duration};
+use futures::{Stream, StreamExt, stream, future::{self, Either}};
+use futures_timer::Delay;
+
+use crate::{
+    error::{Error, Result},
+    envelope::Envelope,
+    event::Event,
+    consumer::Consumer,
+};
+
+
+/// The policy deciding when a [`WindowedConsumer`] closes a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowPolicy {
+    /// Close the window once it holds the given number of envelopes.
+    Count(usize),
+    /// Close the window once the given duration has elapsed since its first envelope.
+    Time(Duration),
+    /// Close the window on whichever of the count or duration is reached first.
+    CountOrTime(usize, Duration),
+}
+
+impl WindowPolicy {
+    fn count(&self) -> Option<usize> {
+        match *self {
+            WindowPolicy::Count(n) | WindowPolicy::CountOrTime(n, _) => Some(n.max(1)),
+            WindowPolicy::Time(_) => None,
+        }
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        match *self {
+            WindowPolicy::Time(d) | WindowPolicy::CountOrTime(_, d) => Some(d),
+            WindowPolicy::Count(_) => None,
+        }
+    }
+}
+
+
+/// A batch of envelopes collected by a [`WindowedConsumer`].
+#[derive(Debug, Clone)]
+pub struct Window {
+    envelopes: Vec<Envelope>,
+}
+
+impl Window {
+    /// Returns the envelopes in the window.
+    pub fn envelopes(&self) -> &[Envelope] {
+        &self.envelopes
+    }
+
+    /// Consume the window and return its envelopes.
+    pub fn into_envelopes(self) -> Vec<Envelope> {
+        self.envelopes
+    }
+
+    /// Returns an iterator over the events in the window.
+    pub fn events(&self) -> impl Iterator<Item = &Event> {
+        self.envelopes.iter().map(Envelope::event)
+    }
+
+    /// Returns the number of envelopes in the window.
+    pub fn len(&self) -> usize {
+        self.envelopes.len()
+    }
+
+    /// Returns whether the window holds no envelopes.
+    pub fn is_empty(&self) -> bool {
+        self.envelopes.is_empty()
+    }
+
+    /// Acknowledge successful processing of every envelope in the window.
+    pub async fn ack(&self) {
+        for envelope in &self.envelopes {
+            envelope.ack().await;
+        }
+    }
+
+    /// Negatively acknowledge failed processing of every envelope in the window.
+    pub async fn nack(&self) {
+        for envelope in &self.envelopes {
+            envelope.nack().await;
+        }
+    }
+}
+
+
+struct WindowState {
+    envelopes: Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>,
+    pending_error: Option<Error>,
+    done: bool,
+}
+
+/// A consumer that groups the envelopes of an inner consumer into windows,
+/// based on a [`WindowPolicy`].
+pub struct WindowedConsumer<C: Consumer> {
+    inner: C,
+    policy: WindowPolicy,
+}
+
+impl<C: Consumer> WindowedConsumer<C> {
+    /// Create a new [`WindowedConsumer`] wrapping the given consumer.
+    ///
+    /// # Arguments
+    /// * `inner` - The consumer to collect envelopes from.
+    /// * `policy` - The policy deciding when a window is closed.
+    ///
+    /// # Returns
+    /// The new [`WindowedConsumer`].
+    pub fn new(inner: C, policy: WindowPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// Returns a reference to the inner consumer.
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    /// Stream windows of envelopes.
+    ///
+    /// A window is opened by its first envelope, so no empty windows are emitted. When
+    /// the inner stream yields an error or ends, the window collected so far is emitted
+    /// first.
+    ///
+    /// # Returns
+    /// A result containing a stream of windows or an error.
+    pub async fn stream_windows(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Window>> + Send>>> {
+        let policy = self.policy;
+        let state = WindowState {
+            envelopes: self.inner.stream_events().await?,
+            pending_error: None,
+            done: false,
+        };
+
+        Ok(Box::pin(stream::unfold(state, move |mut state| async move {
+            if let Some(e) = state.pending_error.take() {
+                return Some((Err(e), state));
+            }
+
+            if state.done {
+                return None;
+            }
+
+            let mut envelopes = Vec::new();
+            let mut delay: Option<Delay> = None;
+
+            loop {
+                let next = match delay.as_mut() {
+                    Some(delay) => match future::select(state.envelopes.next(), delay).await {
+                        Either::Left((next, _)) => next,
+                        Either::Right(_) => break,
+                    },
+                    None => state.envelopes.next().await,
+                };
+
+                match next {
+                    Some(Ok(envelope)) => {
+                        if envelopes.is_empty() {
+                            delay = policy.duration().map(Delay::new);
+                        }
+
+                        envelopes.push(envelope);
+
+                        if policy.count().is_some_and(|n| envelopes.len() >= n) {
+                            break;
+                        }
+                    }
+                    Some(Err(e)) => {
+                        if envelopes.is_empty() {
+                            return Some((Err(e), state));
+                        }
+
+                        state.pending_error = Some(e);
+                        break;
+                    }
+                    None => {
+                        state.done = true;
+
+                        if envelopes.is_empty() {
+                            return None;
+                        }
+
+                        break;
+                    }
+                }
+            }
+
+            Some((Ok(Window { envelopes }), state))
+        })))
+    }
+}
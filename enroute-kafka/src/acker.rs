@@ -0,0 +1,198 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}},
+    fmt::{self, Debug},
+};
+use async_trait::async_trait;
+use rdkafka::{
+    Offset,
+    TopicPartitionList,
+    consumer::{Consumer as _, CommitMode, StreamConsumer},
+};
+
+use enroute_core::{envelope::Acker, error::{Error, Result}};
+
+use crate::context::KafkaConsumerContext;
+
+
+/// The offsets of a partition delivered to the application.
+#[derive(Default)]
+struct PartitionOffsets {
+    /// The offsets delivered but not yet acked, including nacked ones.
+    unacked: BTreeSet<i64>,
+    /// The offset following the last delivered one.
+    next: i64,
+    /// The offset last committed.
+    committed: Option<i64>,
+}
+
+impl PartitionOffsets {
+    /// Returns the offset to commit: the lowest offset not yet acked, or the one
+    /// following the last delivered offset once every delivered offset is acked.
+    fn commit_point(&self) -> i64 {
+        self.unacked
+            .first()
+            .copied()
+            .unwrap_or(self.next)
+    }
+}
+
+/// The offsets delivered to the application by a consumer, shared by the ackers of its
+/// envelopes.
+///
+/// Acks are coalesced per partition into the highest offset below which every delivered
+/// message is acked, so acking out of order never commits past a message still being
+/// processed, and a burst of acks is committed at once. A nacked message is never acked,
+/// holding back its partition's commits until the consumer restarts or the partition is
+/// reassigned, when it is delivered again. Whatever is left uncommitted when the tracker
+/// is dropped is committed one last time.
+pub(crate) struct OffsetTracker {
+    consumer: Arc<StreamConsumer<KafkaConsumerContext>>,
+    partitions: Mutex<HashMap<(String, i32), PartitionOffsets>>,
+}
+
+impl OffsetTracker {
+    pub(crate) fn new(consumer: Arc<StreamConsumer<KafkaConsumerContext>>) -> Self {
+        Self {
+            consumer,
+            partitions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn partitions(&self) -> std::sync::MutexGuard<'_, HashMap<(String, i32), PartitionOffsets>> {
+        self.partitions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Record a message as delivered, so later offsets are not committed before it is
+    /// acked.
+    pub(crate) fn deliver(&self, topic: &str, partition: i32, offset: i64) {
+        let mut partitions = self.partitions();
+        let offsets = partitions
+            .entry((topic.to_string(), partition))
+            .or_default();
+
+        offsets.unacked.insert(offset);
+        // Partitions are delivered in order, also after seeking back.
+        offsets.next = offset + 1;
+    }
+
+    fn ack(&self, topic: &str, partition: i32, offset: i64) {
+        if let Some(offsets) = self.partitions().get_mut(&(topic.to_string(), partition)) {
+            offsets.unacked.remove(&offset);
+        }
+    }
+
+    /// Commit the offset of every partition whose commit point moved since it was last
+    /// committed, if any.
+    pub(crate) fn commit(&self, mode: CommitMode) -> Result<()> {
+        let mut offsets = TopicPartitionList::new();
+
+        for ((topic, partition), partition_offsets) in self.partitions().iter_mut() {
+            let offset = partition_offsets.commit_point();
+
+            if partition_offsets.committed == Some(offset) {
+                continue;
+            }
+
+            offsets
+                .add_partition_offset(topic, *partition, Offset::Offset(offset))
+                .map_err(|e| Error::Consumer(e.to_string()))?;
+            partition_offsets.committed = Some(offset);
+        }
+
+        if offsets.count() == 0 {
+            return Ok(());
+        }
+
+        self.consumer
+            .commit(&offsets, mode)
+            .map_err(|e| Error::Consumer(e.to_string()))
+    }
+}
+
+impl Drop for OffsetTracker {
+    fn drop(&mut self) {
+        let _ = self.commit(CommitMode::Async);
+    }
+}
+
+/// An acker that commits the offset of a Kafka message once it is acked.
+///
+/// Acks are coalesced across the envelopes of a consumer: the offset committed for a
+/// partition is the highest one below which every delivered message is acked, so acking
+/// out of order never skips a message still being processed. Nacking leaves the offset
+/// uncommitted, so the message is delivered again after the consumer restarts or its
+/// partitions are rebalanced. When the consumer commits on an interval, acking only
+/// records the offset for the next periodic commit.
+#[derive(Clone)]
+pub struct KafkaAcker {
+    tracker: Arc<OffsetTracker>,
+    commit_on_ack: bool,
+    topic: String,
+    partition: i32,
+    offset: i64,
+    done: Arc<AtomicBool>,
+}
+
+impl KafkaAcker {
+    pub(crate) fn new(tracker: Arc<OffsetTracker>, topic: String, partition: i32, offset: i64) -> Self {
+        tracker.deliver(&topic, partition, offset);
+
+        Self {
+            tracker,
+            commit_on_ack: true,
+            topic,
+            partition,
+            offset,
+            done: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Set whether acking commits right away, or leaves the commit to the consumer's
+    /// periodic commit.
+    pub(crate) fn with_commit_on_ack(mut self, commit_on_ack: bool) -> Self {
+        self.commit_on_ack = commit_on_ack;
+        self
+    }
+
+    /// Returns the partition of the message.
+    pub fn partition(&self) -> i32 {
+        self.partition
+    }
+
+    /// Returns the offset of the message.
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+}
+
+impl Debug for KafkaAcker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KafkaAcker")
+            .field("topic", &self.topic)
+            .field("partition", &self.partition)
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Acker for KafkaAcker {
+    async fn ack(&self) {
+        if self.done.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        self.tracker.ack(&self.topic, self.partition, self.offset);
+
+        if self.commit_on_ack {
+            let _ = self.tracker.commit(CommitMode::Async);
+        }
+    }
+
+    async fn nack(&self) {
+        self.done.store(true, Ordering::SeqCst);
+    }
+}
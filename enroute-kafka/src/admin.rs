@@ -0,0 +1,120 @@
+use std::{sync::Arc, time::Duration};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use rdkafka::{
+    admin::{AdminClient, AdminOptions, NewTopic, TopicReplication},
+    client::DefaultClientContext,
+    types::RDKafkaErrorCode,
+};
+
+use enroute_core::{
+    admin::Admin,
+    error::{Error, Result},
+};
+
+use crate::consumer::run_blocking;
+
+
+/// The timeout of admin operations and metadata queries.
+const ADMIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An admin managing the topics of a Kafka cluster.
+pub struct KafkaAdmin {
+    client: Arc<AdminClient<DefaultClientContext>>,
+    default_partitions: i32,
+}
+
+impl KafkaAdmin {
+    /// Create a new [`KafkaAdmin`].
+    /// 
+    /// # Arguments
+    /// * `client` - The admin client.
+    /// * `default_partitions` - The number of partitions of topics created with
+    ///   [`Admin::create_topic`].
+    /// 
+    /// # Returns
+    /// The new [`KafkaAdmin`].
+    pub fn new(client: AdminClient<DefaultClientContext>, default_partitions: i32) -> Self {
+        Self {
+            client: Arc::new(client),
+            default_partitions,
+        }
+    }
+
+    /// Returns a reference to the underlying admin client.
+    pub fn client(&self) -> &AdminClient<DefaultClientContext> {
+        &self.client
+    }
+
+    /// Create a topic with the given number of partitions and the cluster's default
+    /// replication factor.
+    /// 
+    /// # Arguments
+    /// * `topic` - The topic to create.
+    /// * `partitions` - The number of partitions of the topic.
+    /// 
+    /// # Returns
+    /// A result indicating success or failure, failing if the topic already exists.
+    pub async fn create_topic_with_partitions(&self, topic: &str, partitions: i32) -> Result<()> {
+        self.create(topic, partitions, false).await
+    }
+
+    pub(crate) async fn create(&self, topic: &str, partitions: i32, ignore_existing: bool) -> Result<()> {
+        let results = self.client
+            .create_topics(
+                &[NewTopic::new(topic, partitions, TopicReplication::Fixed(-1))],
+                &AdminOptions::new().operation_timeout(Some(ADMIN_TIMEOUT)),
+            )
+            .await
+            .map_err(|e| Error::Unknown(anyhow!(e)))?;
+
+        for result in results {
+            match result {
+                Ok(_) => {}
+                Err((_, RDKafkaErrorCode::TopicAlreadyExists)) if ignore_existing => {}
+                Err((topic, code)) => {
+                    return Err(Error::Unknown(anyhow!("failed to create topic {}: {}", topic, code)));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Admin for KafkaAdmin {
+    async fn create_topic(&self, topic: &str) -> Result<()> {
+        self.create(topic, self.default_partitions, false).await
+    }
+
+    async fn delete_topic(&self, topic: &str) -> Result<()> {
+        let results = self.client
+            .delete_topics(&[topic], &AdminOptions::new().operation_timeout(Some(ADMIN_TIMEOUT)))
+            .await
+            .map_err(|e| Error::Unknown(anyhow!(e)))?;
+
+        for result in results {
+            if let Err((topic, code)) = result {
+                return Err(Error::Unknown(anyhow!("failed to delete topic {}: {}", topic, code)));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn list_topics(&self) -> Result<Vec<String>> {
+        let client = self.client.clone();
+
+        run_blocking(move || Ok(
+            client
+                .inner()
+                .fetch_metadata(None, ADMIN_TIMEOUT)
+                .map_err(|e| Error::Unknown(anyhow!(e)))?
+                .topics()
+                .iter()
+                .map(|topic| topic.name().to_string())
+                .collect()
+        )).await
+    }
+}
@@ -1,39 +1,103 @@
-use std::{time::Duration, collections::HashMap};
+use std::{time::Duration, collections::HashMap, sync::{Arc, Mutex}};
 use anyhow::anyhow;
 use async_trait::async_trait;
 use serde::{Serialize, Deserialize};
-use rdkafka::{ClientConfig, producer::FutureProducer, consumer::{Consumer, StreamConsumer}};
+use rdkafka::{
+    ClientConfig,
+    admin::AdminClient,
+    client::DefaultClientContext,
+    producer::FutureProducer,
+    consumer::{Consumer, StreamConsumer},
+};
 
 use enroute_core::{
     publisher::PublisherOptions,
-    consumer::ConsumerOptions,
+    consumer::{ConsumerOptions, DeliveryGuarantee},
     broker::{Broker, BrokerBuilder},
     error::{Error, Result},
 };
 
 use enroute_kafka::{
+    admin::KafkaAdmin,
+    context::KafkaConsumerContext,
     consumer::KafkaConsumer,
-    publisher::KafkaPublisher,
+    publisher::{KafkaPublisher, TimestampSource, DEFAULT_HEADER_PREFIX},
+    transaction::TransactionalPublisher,
+    consumer::run_blocking,
 };
 
 
+/// The timeout used when fetching cluster metadata.
+const METADATA_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where a consumer group without a committed offset starts reading a partition, set as
+/// the consumers' `auto.offset.reset`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OffsetReset {
+    /// Start from the oldest retained message, replaying the partition's history.
+    Earliest,
+    /// Start from the next message published.
+    #[default]
+    Latest,
+}
+
+impl OffsetReset {
+    /// Returns the value of the `auto.offset.reset` setting.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OffsetReset::Earliest => "earliest",
+            OffsetReset::Latest => "latest",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KafkaBrokerConfig {
     pub bootstrap_servers: Vec<String>,
     pub producer_timeout_ms: Option<Duration>,
+    #[serde(default)]
+    pub timestamp_source: TimestampSource,
+    /// Whether consumers parse extensions back into integers and booleans, instead of
+    /// reading every extension as a string.
+    #[serde(default)]
+    pub infer_extension_types: bool,
+    /// Whether topics are created when a publisher or consumer is created for them.
+    /// This requires permission to create topics on the cluster, and is meant for
+    /// development and tests rather than production.
+    #[serde(default)]
+    pub auto_create_topics: bool,
+    /// The number of partitions of automatically created topics.
+    #[serde(default = "default_partitions")]
+    pub default_partitions: i32,
+    /// The prefix of the headers carrying CloudEvent attributes.
+    #[serde(default = "default_header_prefix")]
+    pub header_prefix: String,
+    /// Where new consumer groups start reading, which a consumer's `auto.offset.reset`
+    /// extra option overrides.
+    #[serde(default)]
+    pub auto_offset_reset: OffsetReset,
+}
+
+fn default_partitions() -> i32 {
+    1
+}
+
+fn default_header_prefix() -> String {
+    DEFAULT_HEADER_PREFIX.to_string()
 }
 
 impl KafkaBrokerConfig {
     pub fn into_client_config(&self, options: Option<HashMap<String, String>>) -> ClientConfig {
         let mut config = ClientConfig::new();
 
-        config.set("bootstrap.servers", &self.bootstrap_servers.join(","));
+        config.set("bootstrap.servers", self.bootstrap_servers.join(","));
 
         if let Some(timeout) = self.producer_timeout_ms {
-            config.set("message.timeout.ms", &timeout.as_millis().to_string());
+            config.set("message.timeout.ms", timeout.as_millis().to_string());
         }
 
-        config.extend(options.unwrap_or_default().into_iter());
+        config.extend(options.unwrap_or_default());
 
         config
     }
@@ -43,34 +107,127 @@ impl KafkaBrokerConfig {
 #[derive(Clone)]
 pub struct KafkaBroker {
     config: KafkaBrokerConfig,
+    /// The client fetching cluster metadata, created on first use and shared by clones.
+    metadata_client: Arc<Mutex<Option<Arc<AdminClient<DefaultClientContext>>>>>,
 }
 
 impl KafkaBroker {
     pub fn new(config: KafkaBrokerConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            metadata_client: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn metadata_client(&self) -> Result<Arc<AdminClient<DefaultClientContext>>> {
+        let mut metadata_client = self.metadata_client
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        if let Some(client) = metadata_client.as_ref() {
+            return Ok(client.clone());
+        }
+
+        let client = Arc::new(
+            self.config
+                .into_client_config(None)
+                .create::<AdminClient<DefaultClientContext>>()
+                .map_err(|e| Error::Unknown(anyhow!(e)))?
+        );
+
+        *metadata_client = Some(client.clone());
+        Ok(client)
     }
 
     pub fn builder() -> KafkaBrokerBuilder {
         KafkaBrokerBuilder::new()
     }
 
-    pub fn new_producer(&self) -> Result<FutureProducer> {
-        Ok(
+    pub fn new_producer(&self, extra: &HashMap<String, String>) -> Result<FutureProducer> {
+        self.config
+            .into_client_config(Some(extra.clone()))
+            .create::<FutureProducer>()
+            .map_err(|e| Error::Unknown(anyhow!(e)))
+    }
+
+    /// Create a [`TransactionalPublisher`] with the given options.
+    /// 
+    /// The options must set `transactional.id` in their extra settings.
+    /// 
+    /// # Arguments
+    /// * `options` - The options to configure the publisher.
+    /// 
+    /// # Returns
+    /// A result containing the transactional publisher or an error.
+    pub async fn transactional_publisher(&self, options: PublisherOptions) -> Result<TransactionalPublisher> {
+        if !options.extra.contains_key("transactional.id") {
+            return Err(Error::Builder("transactional.id is required".to_string()));
+        }
+
+        TransactionalPublisher::new(
+            KafkaPublisher::new(
+                self.new_producer(&options.extra)?,
+                options.channel.to_string(),
+                self.config.producer_timeout_ms,
+            )
+            .with_default_extensions(options.default_extensions)
+            .with_strict_channel_check(options.strict_channel_check)
+            .with_timestamp_source(self.config.timestamp_source)
+            .with_header_prefix(self.config.header_prefix.clone()),
+            self.config.producer_timeout_ms.unwrap_or(Duration::from_secs(30)),
+        )
+        .await
+    }
+
+    /// Create the given topic if it doesn't exist yet, when topic auto-creation is enabled.
+    /// 
+    /// The topic is created with the configured default number of partitions and the
+    /// cluster's default replication factor. Existing topics are left untouched.
+    /// 
+    /// # Arguments
+    /// * `topic` - The topic to create.
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn ensure_topic(&self, topic: &str) -> Result<()> {
+        if !self.config.auto_create_topics {
+            return Ok(());
+        }
+
+        self.new_admin()?
+            .create(topic, self.config.default_partitions, true)
+            .await
+    }
+
+    /// Create a [`KafkaAdmin`] for the cluster, creating topics with the configured
+    /// default number of partitions.
+    pub fn new_admin(&self) -> Result<KafkaAdmin> {
+        Ok(KafkaAdmin::new(
             self.config
                 .into_client_config(None)
-                .create::<FutureProducer>()
-                .map_err(|e| Error::Unknown(anyhow!(e)))?
-        )
+                .create::<AdminClient<DefaultClientContext>>()
+                .map_err(|e| Error::Unknown(anyhow!(e)))?,
+            self.config.default_partitions,
+        ))
     }
 
-    pub fn new_consumer(&self, topic: &str, consumer_tag: &str) -> Result<StreamConsumer> {
+    pub fn new_consumer(
+        &self,
+        topic: &str,
+        consumer_tag: &str,
+        extra: &HashMap<String, String>,
+        delivery_guarantee: Option<DeliveryGuarantee>,
+    ) -> Result<StreamConsumer<KafkaConsumerContext>> {
+        let mut options = HashMap::from([
+            ("group.id".to_string(), consumer_tag.to_string()),
+            ("enable.auto.commit".to_string(), delivery_guarantee.is_none().to_string()),
+            ("auto.offset.reset".to_string(), self.config.auto_offset_reset.as_str().to_string()),
+        ]);
+        options.extend(extra.clone());
+
         let consumer = self.config
-            .into_client_config(Some(HashMap::from([
-                ("group.id".to_string(), consumer_tag.to_string()),
-                ("enable.auto.commit".to_string(), "true".to_string()),
-                ("auto.offset.reset".to_string(), "earliest".to_string()),
-            ])))
-            .create::<StreamConsumer>()
+            .into_client_config(Some(options))
+            .create_with_context::<_, StreamConsumer<KafkaConsumerContext>>(KafkaConsumerContext::default())
             .map_err(|e| Error::Unknown(anyhow!(e)))?;
 
         consumer
@@ -85,19 +242,61 @@ impl KafkaBroker {
 impl Broker for KafkaBroker {
     type Publisher = KafkaPublisher;
     type Consumer = KafkaConsumer;
+    type Admin = KafkaAdmin;
 
     async fn publisher(&self, options: PublisherOptions) -> Result<Self::Publisher> {
-        Ok(KafkaPublisher::new(
-            self.new_producer()?,
-            options.channel.to_string(),
-            self.config.producer_timeout_ms,
-        ))
+        self.ensure_topic(&options.channel).await?;
+
+        Ok(
+            KafkaPublisher::new(
+                self.new_producer(&options.extra)?,
+                options.channel.to_string(),
+                self.config.producer_timeout_ms,
+            )
+            .with_default_extensions(options.default_extensions)
+            .with_strict_channel_check(options.strict_channel_check)
+            .with_timestamp_source(self.config.timestamp_source)
+            .with_header_prefix(self.config.header_prefix.clone())
+        )
     }
 
     async fn consumer(&self, options: ConsumerOptions) -> Result<Self::Consumer> {
-        Ok(KafkaConsumer::new(
-            self.new_consumer(&options.channel, &options.consumer_tag)?
-        ))
+        self.ensure_topic(&options.channel).await?;
+
+        Ok(
+            KafkaConsumer::new(
+                self.new_consumer(
+                    &options.channel,
+                    &options.consumer_tag,
+                    &options.extra,
+                    options.delivery_guarantee,
+                )?,
+                options.channel.to_string(),
+            )
+            .with_delivery_guarantee(options.delivery_guarantee)
+            .with_max_in_flight(options.max_in_flight)
+            .with_infer_extension_types(self.config.infer_extension_types)
+            .with_header_prefix(self.config.header_prefix.clone())
+        )
+    }
+
+    async fn channels(&self) -> Result<Vec<String>> {
+        let client = self.metadata_client()?;
+
+        run_blocking(move || Ok(
+            client
+                .inner()
+                .fetch_metadata(None, METADATA_TIMEOUT)
+                .map_err(|e| Error::Unknown(anyhow!(e)))?
+                .topics()
+                .iter()
+                .map(|topic| topic.name().to_string())
+                .collect()
+        )).await
+    }
+
+    async fn admin(&self) -> Result<Self::Admin> {
+        self.new_admin()
     }
 }
 
@@ -105,6 +304,12 @@ impl Broker for KafkaBroker {
 pub struct KafkaBrokerBuilder {
     bootstrap_servers: Option<Vec<String>>,
     producer_timeout_ms: Option<Duration>,
+    timestamp_source: TimestampSource,
+    infer_extension_types: bool,
+    auto_create_topics: bool,
+    default_partitions: i32,
+    header_prefix: String,
+    auto_offset_reset: OffsetReset,
 }
 
 impl KafkaBrokerBuilder {
@@ -112,6 +317,12 @@ impl KafkaBrokerBuilder {
         Self {
             bootstrap_servers: None,
             producer_timeout_ms: None,
+            timestamp_source: TimestampSource::default(),
+            infer_extension_types: false,
+            auto_create_topics: false,
+            default_partitions: default_partitions(),
+            header_prefix: default_header_prefix(),
+            auto_offset_reset: OffsetReset::default(),
         }
     }
 
@@ -124,6 +335,49 @@ impl KafkaBrokerBuilder {
         self.producer_timeout_ms = Some(timeout);
         self
     }
+
+    /// Set the source of the timestamp set on records by the broker's publishers.
+    pub fn with_timestamp_source(mut self, source: TimestampSource) -> Self {
+        self.timestamp_source = source;
+        self
+    }
+
+    /// Set whether the broker's consumers parse extensions back into integers and booleans.
+    pub fn with_infer_extension_types(mut self, infer: bool) -> Self {
+        self.infer_extension_types = infer;
+        self
+    }
+
+    /// Set whether topics are created when a publisher or consumer is created for them.
+    /// Meant for development and tests, as it requires permission to create topics.
+    pub fn with_auto_create_topics(mut self, auto_create: bool) -> Self {
+        self.auto_create_topics = auto_create;
+        self
+    }
+
+    /// Set the number of partitions of automatically created topics.
+    pub fn with_default_partitions(mut self, partitions: i32) -> Self {
+        self.default_partitions = partitions;
+        self
+    }
+
+    /// Set the prefix of the headers carrying CloudEvent attributes, `ce-` by default.
+    pub fn with_header_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.header_prefix = prefix.into();
+        self
+    }
+
+    /// Set where new consumer groups start reading, [`OffsetReset::Latest`] by default.
+    pub fn with_auto_offset_reset(mut self, reset: OffsetReset) -> Self {
+        self.auto_offset_reset = reset;
+        self
+    }
+}
+
+impl Default for KafkaBrokerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[async_trait]
@@ -136,6 +390,12 @@ impl BrokerBuilder for KafkaBrokerBuilder {
                 .clone()
                 .ok_or_else(|| Error::Builder("missing bootstrap_servers".to_string()))?,
             producer_timeout_ms: self.producer_timeout_ms,
+            timestamp_source: self.timestamp_source,
+            infer_extension_types: self.infer_extension_types,
+            auto_create_topics: self.auto_create_topics,
+            default_partitions: self.default_partitions,
+            header_prefix: self.header_prefix.clone(),
+            auto_offset_reset: self.auto_offset_reset,
         }))
     } 
 }
\ No newline at end of file
@@ -1,20 +1,49 @@
-use std::{sync::Arc, pin::Pin, collections::HashMap};
+use std::{
+    sync::{Arc, Weak, atomic::{AtomicBool, Ordering}},
+    pin::Pin,
+    collections::HashMap,
+    time::Duration,
+};
 use async_trait::async_trait;
 use async_stream::stream;
-use futures::{Stream, StreamExt};
+use futures::{Stream, StreamExt, future, stream};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
-use rdkafka::{consumer::StreamConsumer, message::{Message, Headers, Header, BorrowedMessage}};
+use rdkafka::{
+    Offset,
+    TopicPartitionList,
+    consumer::{Consumer as _, CommitMode, ConsumerGroupMetadata, StreamConsumer},
+    error::{KafkaError, KafkaResult, RDKafkaErrorCode},
+    message::{Message, Headers, Header},
+    util::{AsyncRuntime, DefaultRuntime},
+};
 
 use enroute_core::{
-    consumer::Consumer,
-    event::Event,
-    envelope::Envelope,
+    consumer::{Consumer, ConsumerEvent, DeliveryGuarantee},
+    in_flight::limit_in_flight,
+    event::{Event, ExtensionValue, parse_extension_value},
+    clock::{Clock, SystemClock},
+    envelope::{Acker, Envelope, NoOpAcker, TOMBSTONE_EXTENSION},
     error::{Error, Result},
 };
 
+use crate::{acker::{KafkaAcker, OffsetTracker}, context::KafkaConsumerContext, publisher::DEFAULT_HEADER_PREFIX};
+
+
+/// The CloudEvent attributes carried in headers, without their prefix.
+const ATTRIBUTE_HEADERS: [&str; 8] = [
+    "type",
+    "source",
+    "id",
+    "subject",
+    "time",
+    "specversion",
+    "dataschema",
+    "datacontenttype",
+];
+
 
-fn try_get_header_str(msg: &BorrowedMessage, key: &str) -> Option<String> {
+fn try_get_header_str(msg: &impl Message, key: &str) -> Option<String> {
     msg.headers()?
         .iter()
         .find(|h| h.key == key)
@@ -22,11 +51,29 @@ fn try_get_header_str(msg: &BorrowedMessage, key: &str) -> Option<String> {
         .map(|v| String::from_utf8_lossy(v).to_string())
 }
 
-fn get_header_str(msg: &BorrowedMessage, key: &str) -> Result<String> {
+fn get_header_str(msg: &impl Message, key: &str) -> Result<String> {
     try_get_header_str(msg, key)
         .ok_or_else(|| Error::Deserialization(format!("Missing {} header", key)))
 }
 
+/// Returns every value of a header of a message, in order, for headers repeated with
+/// the same key.
+/// 
+/// # Arguments
+/// * `msg` - The message to read the header of.
+/// * `key` - The key of the header.
+/// 
+/// # Returns
+/// The values of the header, empty if the message has no such header.
+pub fn get_header_all(msg: &impl Message, key: &str) -> Vec<String> {
+    msg.headers()
+        .map(|headers| headers.iter()
+            .filter(|h| h.key == key)
+            .filter_map(|h| Some(String::from_utf8_lossy(h.value?).to_string()))
+            .collect())
+        .unwrap_or_default()
+}
+
 // fn filtered_headers(msg: &BorrowedMessage, exclude_keys: &[&str]) -> HashMap<String, String> {
 //     msg.headers()
 //         .map(|headers| headers.iter()
@@ -36,7 +83,9 @@ fn get_header_str(msg: &BorrowedMessage, key: &str) -> Result<String> {
 //         .unwrap_or_default()
 // }
 
-fn filtered_headers<F>(msg: &BorrowedMessage, filter: F) -> HashMap<String, String>
+/// Collects the headers accepted by the filter, joining the values of headers repeated
+/// with the same key with commas, in order.
+fn filtered_headers<F>(msg: &impl Message, filter: F) -> HashMap<String, String>
 where
     F: FnMut(&Header<'_, &[u8]>) -> bool,
 {
@@ -44,75 +93,717 @@ where
         .map(|headers| headers.iter()
             .filter(filter)
             .filter_map(|h| Some((h.key.to_string(), String::from_utf8_lossy(h.value?).to_string())))
-            .collect())
+            .fold(HashMap::new(), |mut headers: HashMap<String, String>, (key, value)| {
+                headers
+                    .entry(key)
+                    .and_modify(|joined| {
+                        joined.push(',');
+                        joined.push_str(&value);
+                    })
+                    .or_insert(value);
+                headers
+            }))
         .unwrap_or_default()
 }
 
+/// The event type of tombstones produced without a type header.
+pub const TOMBSTONE_EVENT_TYPE: &str = "tombstone";
+
+/// Returns the time of the event carried by a message: the time attribute header, sent
+/// whatever the publisher's timestamp source, falling back to the record timestamp.
+fn message_time(msg: &impl Message, header_prefix: &str, clock: &dyn Clock) -> DateTime<Utc> {
+    try_get_header_str(msg, &format!("{}time", header_prefix))
+        .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+        .map(|time| time.with_timezone(&Utc))
+        .or_else(|| msg
+            .timestamp()
+            .to_millis()
+            .and_then(DateTime::<Utc>::from_timestamp_millis)
+        )
+        .unwrap_or_else(|| clock.now())
+}
+
+/// Convert a record without a value into a tombstone event, carrying the record key as
+/// its subject and no data.
+fn tombstone_to_event(msg: &impl Message, header_prefix: &str, clock: &dyn Clock) -> Result<Event> {
+    let key = msg
+        .key()
+        .map(|k| String::from_utf8_lossy(k).to_string());
+
+    Event::builder()
+        .id(
+            try_get_header_str(msg, &format!("{}id", header_prefix))
+                .or_else(|| key.clone())
+                .unwrap_or_else(|| Uuid::new_v4().to_string())
+        )
+        .time(message_time(msg, header_prefix, clock))
+        .type_(
+            try_get_header_str(msg, &format!("{}type", header_prefix))
+                .unwrap_or_else(|| TOMBSTONE_EVENT_TYPE.to_string())
+        )
+        .source(
+            try_get_header_str(msg, &format!("{}source", header_prefix))
+                .unwrap_or_else(|| msg.topic().to_string())
+        )
+        .maybe_subject(key)
+        .extension(TOMBSTONE_EXTENSION, true)
+        .rebuild()
+}
+
+fn message_to_event(msg: &impl Message, header_prefix: &str, infer_extension_types: bool, clock: &dyn Clock) -> Result<Event> {
+    if msg.payload().is_none() {
+        return tombstone_to_event(msg, header_prefix, clock);
+    }
+
+    let extensions = filtered_headers(
+        msg,
+        |h| !h.key
+            .strip_prefix(header_prefix)
+            .is_some_and(|attribute| ATTRIBUTE_HEADERS.contains(&attribute))
+    );
+    let event_type = get_header_str(msg, &format!("{}type", header_prefix))?;
+    let content_type = try_get_header_str(msg, &format!("{}datacontenttype", header_prefix))
+        .unwrap_or_else(|| "application/json".to_string());
+
+    Event::builder()
+        .id(
+            try_get_header_str(msg, &format!("{}id", header_prefix))
+                .or_else(|| msg.key().map(|k| String::from_utf8_lossy(k).to_string()))
+                .unwrap_or_else(|| Uuid::new_v4().to_string())
+        )
+        .time(message_time(msg, header_prefix, clock))
+        .type_(event_type.as_str())
+        .source(get_header_str(msg, &format!("{}source", header_prefix))?.as_str())
+        .maybe_subject(try_get_header_str(msg, &format!("{}subject", header_prefix)))
+        .maybe_schema_url(
+            try_get_header_str(msg, &format!("{}dataschema", header_prefix))
+                .as_deref()
+        )
+        .extensions(
+            extensions
+                .into_iter()
+                .map(|(k, v)| if infer_extension_types {
+                    (k, parse_extension_value(&v))
+                } else {
+                    (k, ExtensionValue::String(v))
+                })
+                .collect::<HashMap<_, _>>()
+        )
+        .build_raw_with_content_type(
+            &event_type,
+            msg
+                .payload()
+                .unwrap_or_default()
+                .to_vec(),
+            &content_type,
+        )
+}
+
+/// Returns whether a consumption error means the connection to the cluster failed, as
+/// opposed to an error with a single message.
+fn is_connection_failure(error: &KafkaError) -> bool {
+    match error {
+        KafkaError::MessageConsumptionFatal(_) => true,
+        KafkaError::MessageConsumption(code) => matches!(
+            code,
+            RDKafkaErrorCode::AllBrokersDown
+                | RDKafkaErrorCode::BrokerTransportFailure
+                | RDKafkaErrorCode::Resolve
+                | RDKafkaErrorCode::Fatal
+        ),
+        _ => false,
+    }
+}
+
+/// Decode a stream of messages into events, each paired with its message.
+/// 
+/// A malformed message is reported without ending the stream, so the caller can skip it
+/// and keep consuming.
+fn decode_messages<'a, M: Message + 'a>(
+    messages: impl Stream<Item = KafkaResult<M>> + 'a,
+    header_prefix: String,
+    infer_extension_types: bool,
+    clock: Arc<dyn Clock>,
+) -> impl Stream<Item = Result<(Event, M)>> + 'a {
+    messages.map(move |message| {
+        let message = message.map_err(|e| Error::Consumer(e.to_string()))?;
+        let event = message_to_event(&message, &header_prefix, infer_extension_types, clock.as_ref())?;
+
+        Ok((event, message))
+    })
+}
+
+/// Commit the pending offsets of the tracker every interval, until it is dropped.
+fn spawn_periodic_commit(offsets: Weak<OffsetTracker>, interval: Duration) {
+    DefaultRuntime::spawn(async move {
+        loop {
+            DefaultRuntime::delay_for(interval).await;
+
+            match offsets.upgrade() {
+                Some(offsets) => {
+                    let _ = offsets.commit(CommitMode::Async);
+                }
+                None => break,
+            }
+        }
+    });
+}
+
+/// The timeout used when querying the cluster for offsets and watermarks.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run a blocking rdkafka call on the blocking thread pool, so waiting on the cluster
+/// doesn't stall the executor.
+pub(crate) async fn run_blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| Error::Unknown(e.into()))?
+}
+
+/// The lag of a partition assigned to a consumer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartitionLag {
+    pub partition: i32,
+    /// The committed offset, or the low watermark if nothing was committed yet.
+    pub committed: i64,
+    pub high_watermark: i64,
+    /// The number of messages between the committed offset and the high watermark.
+    pub lag: i64,
+}
+
+/// The lag of a consumer, over the partitions of its topic assigned to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumerLag {
+    pub topic: String,
+    pub partitions: Vec<PartitionLag>,
+}
+
+impl ConsumerLag {
+    /// Returns the lag summed over every partition.
+    pub fn total(&self) -> i64 {
+        self.partitions
+            .iter()
+            .map(|p| p.lag)
+            .sum()
+    }
+}
+
+fn consumer_lag(consumer: &StreamConsumer<KafkaConsumerContext>, topic: &str) -> Result<ConsumerLag> {
+    let committed = consumer
+        .committed(QUERY_TIMEOUT)
+        .map_err(|e| Error::Consumer(e.to_string()))?;
+    let mut partitions = Vec::new();
+
+    for element in committed.elements_for_topic(topic) {
+        let (low, high) = consumer
+            .fetch_watermarks(topic, element.partition(), QUERY_TIMEOUT)
+            .map_err(|e| Error::Consumer(e.to_string()))?;
+        let committed = element
+            .offset()
+            .to_raw()
+            .filter(|offset| *offset >= 0)
+            .unwrap_or(low);
+
+        partitions.push(PartitionLag {
+            partition: element.partition(),
+            committed,
+            high_watermark: high,
+            lag: (high - committed).max(0),
+        });
+    }
+
+    Ok(ConsumerLag { topic: topic.to_string(), partitions })
+}
+
+/// A Kafka consumer streaming the events of a single topic.
+/// 
+/// A consumer supports a single event stream: once [`Consumer::stream_events`] has been
+/// called, further calls fail. To process events across tasks, share the envelopes of
+/// the one stream, or create a consumer per task with the same consumer tag.
+/// 
+/// Records without a value, the tombstones of compacted topics, are yielded as events
+/// without data for which [`Envelope::is_tombstone`] returns `true`, with the record key
+/// as their subject.
+/// 
+/// Headers other than the prefixed attribute headers are read as extensions. Headers
+/// repeated with the same key are read as a single extension joining their values with
+/// commas, while repeated attribute headers are read from their first value (see
+/// [`get_header_all`] to read every value).
+/// 
+/// Messages that can't be decoded into events, e.g. for a missing `type` header, are
+/// yielded as errors without ending the stream, so callers can skip them and carry on
+/// with the following messages.
 pub struct KafkaConsumer {
-    stream: Arc<StreamConsumer>,
+    stream: Arc<StreamConsumer<KafkaConsumerContext>>,
+    streaming: AtomicBool,
+    connection_failed: Arc<AtomicBool>,
+    topic: String,
+    delivery_guarantee: Option<DeliveryGuarantee>,
+    max_in_flight: Option<usize>,
+    commit_interval: Option<Duration>,
+    offsets: Arc<OffsetTracker>,
+    infer_extension_types: bool,
+    header_prefix: String,
+    clock: Arc<dyn Clock>,
 }
 
 impl KafkaConsumer {
-    pub fn new(stream: StreamConsumer) -> Self {
-        Self { stream: Arc::new(stream) }
+    pub fn new(stream: StreamConsumer<KafkaConsumerContext>, topic: String) -> Self {
+        let stream = Arc::new(stream);
+
+        Self {
+            offsets: Arc::new(OffsetTracker::new(stream.clone())),
+            stream,
+            streaming: AtomicBool::new(false),
+            connection_failed: Arc::new(AtomicBool::new(false)),
+            topic,
+            delivery_guarantee: None,
+            max_in_flight: None,
+            commit_interval: None,
+            infer_extension_types: false,
+            header_prefix: DEFAULT_HEADER_PREFIX.to_string(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Set the delivery guarantee of the consumer.
+    /// 
+    /// With [`DeliveryGuarantee::AtMostOnce`] the offset of each message is committed
+    /// before its envelope is yielded. With [`DeliveryGuarantee::AtLeastOnce`] the offset
+    /// is only committed when the envelope is acked. Either way the underlying consumer
+    /// must be created with `enable.auto.commit` disabled.
+    pub fn with_delivery_guarantee(mut self, guarantee: Option<DeliveryGuarantee>) -> Self {
+        self.delivery_guarantee = guarantee;
+        self
+    }
+
+    /// Set the maximum number of envelopes yielded but not yet acked or nacked.
+    /// 
+    /// While the limit is reached the consumer stops polling, so envelopes must be
+    /// settled within `max.poll.interval.ms` to keep the consumer in its group.
+    pub fn with_max_in_flight(mut self, max: Option<usize>) -> Self {
+        self.max_in_flight = max;
+        self
+    }
+
+    /// Set the interval at which acked offsets are committed.
+    /// 
+    /// With [`DeliveryGuarantee::AtLeastOnce`], acking an envelope then only records its
+    /// offset, and the offset below which every delivered message of each partition is
+    /// acked is committed every interval by a background task. This trades commits on
+    /// each ack for at most an interval of messages delivered again after a crash. Call
+    /// [`KafkaConsumer::close`] before shutting down to commit the offsets acked since the
+    /// last interval.
+    pub fn with_commit_interval(mut self, interval: Option<Duration>) -> Self {
+        self.commit_interval = interval;
+        self
+    }
+
+    /// Set whether extensions are parsed back into integers and booleans.
+    /// 
+    /// Kafka headers only carry strings, so by default every extension is read back as a
+    /// string. When enabled, headers are parsed with [`parse_extension_value`] instead,
+    /// which also types string extensions that happen to look like integers or booleans.
+    pub fn with_infer_extension_types(mut self, infer: bool) -> Self {
+        self.infer_extension_types = infer;
+        self
+    }
+
+    /// Set the prefix of the headers carrying CloudEvent attributes, `ce-` by default.
+    /// 
+    /// Headers with the prefix that name a CloudEvent attribute are read as attributes,
+    /// while every other header is read as an extension.
+    pub fn with_header_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.header_prefix = prefix.into();
+        self
+    }
+
+    /// Set the clock used to stamp events of messages without a timestamp.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Returns the delivery guarantee of the consumer, if one is set.
+    pub fn delivery_guarantee(&self) -> Option<DeliveryGuarantee> {
+        self.delivery_guarantee
+    }
+
+    /// Returns the topic the consumer is subscribed to.
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Returns the committed offset of each partition assigned to the consumer.
+    /// 
+    /// Partitions without a committed offset are reported with a negative offset.
+    /// 
+    /// # Returns
+    /// A result containing `(partition, offset)` pairs or an error.
+    pub async fn committed(&self) -> Result<Vec<(i32, i64)>> {
+        let consumer = self.stream.clone();
+        let topic = self.topic.clone();
+
+        run_blocking(move || Ok(
+            consumer
+                .committed(QUERY_TIMEOUT)
+                .map_err(|e| Error::Consumer(e.to_string()))?
+                .elements_for_topic(&topic)
+                .iter()
+                .map(|e| (e.partition(), e.offset().to_raw().unwrap_or(-1)))
+                .collect()
+        )).await
+    }
+
+    /// Returns the low and high watermarks of a partition of the consumer's topic.
+    /// 
+    /// # Arguments
+    /// * `partition` - The partition to fetch the watermarks of.
+    /// 
+    /// # Returns
+    /// A result containing the `(low, high)` watermarks or an error.
+    pub async fn watermarks(&self, partition: i32) -> Result<(i64, i64)> {
+        let consumer = self.stream.clone();
+        let topic = self.topic.clone();
+
+        run_blocking(move || consumer
+            .fetch_watermarks(&topic, partition, QUERY_TIMEOUT)
+            .map_err(|e| Error::Consumer(e.to_string()))
+        ).await
+    }
+
+    /// Returns the lag of the consumer, comparing the committed offset of each assigned
+    /// partition with its high watermark.
+    /// 
+    /// # Returns
+    /// A result containing the lag or an error.
+    pub async fn lag(&self) -> Result<ConsumerLag> {
+        let consumer = self.stream.clone();
+        let topic = self.topic.clone();
+
+        run_blocking(move || consumer_lag(&consumer, &topic)).await
+    }
+
+    /// Spawn a task computing the lag of the consumer every interval, calling the given
+    /// function whenever the total lag exceeds the threshold, e.g. to drive autoscaling.
+    /// 
+    /// The task runs until the consumer and its streams are dropped. Intervals for which
+    /// the lag can't be queried are skipped.
+    /// 
+    /// # Arguments
+    /// * `interval` - The interval between lag queries.
+    /// * `threshold` - The total lag above which the function is called.
+    /// * `on_lag` - The function called with the lag.
+    pub fn spawn_lag_monitor<F>(&self, interval: Duration, threshold: i64, on_lag: F)
+    where
+        F: Fn(ConsumerLag) + Send + 'static,
+    {
+        let consumer = Arc::downgrade(&self.stream);
+        let topic = self.topic.clone();
+
+        DefaultRuntime::spawn(async move {
+            loop {
+                DefaultRuntime::delay_for(interval).await;
+
+                let Some(consumer) = consumer.upgrade() else {
+                    break;
+                };
+                let topic = topic.clone();
+
+                if let Ok(lag) = run_blocking(move || consumer_lag(&consumer, &topic)).await {
+                    if lag.total() > threshold {
+                        on_lag(lag);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Move the consumer to the given offset of a partition of its topic, to reprocess
+    /// or skip messages without recreating the consumer.
+    /// 
+    /// The partition must be assigned to the consumer. Messages already fetched before
+    /// the seek may still be delivered before it takes effect.
+    /// 
+    /// # Arguments
+    /// * `partition` - The partition to seek in.
+    /// * `offset` - The offset of the next message to consume.
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn seek(&self, partition: i32, offset: i64) -> Result<()> {
+        let consumer = self.stream.clone();
+        let topic = self.topic.clone();
+
+        run_blocking(move || consumer
+            .seek(&topic, partition, Offset::Offset(offset), QUERY_TIMEOUT)
+            .map_err(|e| Error::Consumer(e.to_string()))
+        ).await
+    }
+
+    /// Move the consumer to the beginning of every partition of its topic assigned to
+    /// it (see [`KafkaConsumer::seek`]).
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn seek_to_beginning(&self) -> Result<()> {
+        let consumer = self.stream.clone();
+        let topic = self.topic.clone();
+
+        run_blocking(move || {
+            let assignment = consumer
+                .assignment()
+                .map_err(|e| Error::Consumer(e.to_string()))?;
+
+            for element in assignment.elements_for_topic(&topic) {
+                consumer
+                    .seek(&topic, element.partition(), Offset::Beginning, QUERY_TIMEOUT)
+                    .map_err(|e| Error::Consumer(e.to_string()))?;
+            }
+
+            Ok(())
+        }).await
+    }
+
+    /// Returns whether the event stream has yielded a fatal error or a transport failure
+    /// of the Kafka client, after which the consumer should be recreated.
+    pub(crate) fn connection_failed(&self) -> bool {
+        self.connection_failed.load(Ordering::SeqCst)
+    }
+
+    /// Returns the metadata of the consumer group, used to send offsets to a transaction.
+    pub fn group_metadata(&self) -> Option<ConsumerGroupMetadata> {
+        self.stream.group_metadata()
+    }
+
+    /// Returns the current position (next offset to be consumed) of each assigned partition.
+    pub fn position(&self) -> Result<TopicPartitionList> {
+        self.stream
+            .position()
+            .map_err(|e| Error::Consumer(e.to_string()))
     }
 }
 
 #[async_trait]
 impl Consumer for KafkaConsumer {
     async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
+        if self.streaming.swap(true, Ordering::SeqCst) {
+            return Err(Error::Consumer("consumer is already streaming events".to_string()));
+        }
+
         let consumer = self.stream.clone();
+        let delivery_guarantee = self.delivery_guarantee;
+        let infer_extension_types = self.infer_extension_types;
+        let header_prefix = self.header_prefix.clone();
+        let clock = self.clock.clone();
+        let tracker = self.offsets.clone();
+        let connection_failed = self.connection_failed.clone();
+        let commit_on_ack = match (delivery_guarantee, self.commit_interval) {
+            (Some(DeliveryGuarantee::AtLeastOnce), Some(interval)) => {
+                spawn_periodic_commit(Arc::downgrade(&self.offsets), interval);
+                false
+            }
+            _ => true,
+        };
         let stream = stream! {
-            let mut message_stream = consumer.stream();
-
-            while let Some(message) = message_stream.next().await {
-                match message {
-                    Ok(borrowed_msg) => yield Ok(Envelope::noop(
-                        Event::builder()
-                            .id(
-                                borrowed_msg
-                                    .key()
-                                    .map(|k| String::from_utf8_lossy(k).to_string())
-                                    .unwrap_or_else(|| Uuid::new_v4().to_string())
-                            )
-                            .time(
-                                borrowed_msg
-                                    .timestamp()
-                                    .to_millis()
-                                    .map(|ms| DateTime::<Utc>::from_timestamp_millis(ms))
-                                    .flatten()
-                                    .unwrap_or_else(|| Utc::now())
-                            )
-                            .type_(get_header_str(&borrowed_msg, "ce-type")?.as_str())
-                            .source(get_header_str(&borrowed_msg, "ce-source")?.as_str()) 
-                            .maybe_schema_url(
-                                try_get_header_str(&borrowed_msg, "ce-dataschema")
-                                    .as_deref()
-                            )
-                            .extensions(filtered_headers(
-                                &borrowed_msg,
-                                |h| ![
-                                    "ce-type",
-                                    "ce-source",
-                                    "ce-id",
-                                    "ce-time",
-                                    "ce-specversion",
-                                    "ce-dataschema",
-                                    "ce-datacontenttype",
-                                ].contains(&h.key)
-                            ))
-                            .build_raw(
-                                borrowed_msg
-                                    .payload()
-                                    .unwrap_or_default()
-                                    .to_vec()
-                            )?,
-                    )),
-                    Err(e) => yield Err(Error::Consumer(e.to_string())),
-                }
+            let messages = consumer
+                .stream()
+                .inspect(|message| {
+                    if message.as_ref().is_err_and(is_connection_failure) {
+                        connection_failed.store(true, Ordering::SeqCst);
+                    }
+                });
+            let mut messages = Box::pin(decode_messages(messages, header_prefix, infer_extension_types, clock));
+
+            while let Some(decoded) = messages.next().await {
+                let (event, borrowed_msg) = match decoded {
+                    Ok(decoded) => decoded,
+                    Err(e) => {
+                        yield Err(e);
+                        continue;
+                    }
+                };
+                let acker: Arc<dyn Acker> = match delivery_guarantee {
+                    Some(DeliveryGuarantee::AtMostOnce) => {
+                        if let Err(e) = consumer.commit_message(&borrowed_msg, CommitMode::Async) {
+                            yield Err(Error::Consumer(e.to_string()));
+                            continue;
+                        }
+
+                        Arc::new(NoOpAcker)
+                    }
+                    Some(DeliveryGuarantee::AtLeastOnce) => Arc::new(KafkaAcker::new(
+                        tracker.clone(),
+                        borrowed_msg.topic().to_string(),
+                        borrowed_msg.partition(),
+                        borrowed_msg.offset(),
+                    ).with_commit_on_ack(commit_on_ack)),
+                    None => Arc::new(NoOpAcker),
+                };
+
+                yield Ok(Envelope::new(event, acker));
             }
         };
 
-        Ok(Box::pin(stream))
+        Ok(match self.max_in_flight {
+            Some(max) => limit_in_flight(Box::pin(stream), max),
+            None => Box::pin(stream),
+        })
+    }
+
+    /// Stream incoming events interleaved with the partitions assigned to and revoked
+    /// from the consumer by rebalances. Like [`Consumer::stream_events`], this may only
+    /// be called once per consumer.
+    async fn stream_with_control(&self) -> Result<Pin<Box<dyn Stream<Item = Result<ConsumerEvent>> + Send>>> {
+        let envelopes = self.stream_events()
+            .await?
+            .map(|envelope| Some(envelope.map(ConsumerEvent::Message)))
+            .chain(stream::once(future::ready(None)));
+        let control = self.stream
+            .context()
+            .subscribe()
+            .map(|event| Some(Ok(event)));
+
+        Ok(Box::pin(
+            stream::select(envelopes, control)
+                .take_while(|event| future::ready(event.is_some()))
+                .filter_map(future::ready)
+        ))
+    }
+
+    /// Pause fetching from the partitions currently assigned to the consumer, keeping
+    /// its group membership. Partitions assigned by a later rebalance are not paused.
+    async fn pause(&self) -> Result<()> {
+        let assignment = self.stream
+            .assignment()
+            .map_err(|e| Error::Consumer(e.to_string()))?;
+
+        self.stream
+            .pause(&assignment)
+            .map_err(|e| Error::Consumer(e.to_string()))
+    }
+
+    async fn resume(&self) -> Result<()> {
+        let assignment = self.stream
+            .assignment()
+            .map_err(|e| Error::Consumer(e.to_string()))?;
+
+        self.stream
+            .resume(&assignment)
+            .map_err(|e| Error::Consumer(e.to_string()))
+    }
+
+    /// Commit the offsets acked since the last periodic commit (see
+    /// [`KafkaConsumer::with_commit_interval`]), waiting for the commit to complete.
+    async fn close(&self) -> Result<()> {
+        let offsets = self.offsets.clone();
+
+        run_blocking(move || offsets.commit(CommitMode::Sync)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+    use futures::{StreamExt, stream};
+    use rdkafka::{
+        Timestamp,
+        message::{Header, OwnedHeaders, OwnedMessage},
+        mocking::MockCluster,
+    };
+
+    use enroute_core::{
+        broker::{Broker, BrokerBuilder},
+        consumer::{Consumer, ConsumerOptions},
+        publisher::{Publisher, PublisherOptions},
+        signing::{SigningKey, SigningPublisher},
+        event::EventBuilder,
+        clock::SystemClock,
+    };
+
+    use crate::{broker::{KafkaBroker, OffsetReset}, publisher::DEFAULT_HEADER_PREFIX};
+    use super::decode_messages;
+
+    #[tokio::test]
+    async fn yields_an_error_for_a_malformed_message_and_keeps_decoding() {
+        let message = |headers: OwnedHeaders| OwnedMessage::new(
+            Some(b"{}".to_vec()),
+            None,
+            "orders".to_string(),
+            Timestamp::NotAvailable,
+            0,
+            0,
+            Some(headers),
+        );
+        let malformed = message(OwnedHeaders::new());
+        let valid = message(
+            OwnedHeaders::new()
+                .insert(Header { key: "ce-id", value: Some("order-1") })
+                .insert(Header { key: "ce-type", value: Some("order.placed") })
+                .insert(Header { key: "ce-source", value: Some("test") })
+        );
+
+        let decoded = decode_messages(
+            stream::iter([Ok(malformed), Ok(valid)]),
+            DEFAULT_HEADER_PREFIX.to_string(),
+            false,
+            Arc::new(SystemClock),
+        )
+        .collect::<Vec<_>>()
+        .await;
+
+        assert_eq!(decoded.len(), 2);
+        assert!(decoded[0].is_err());
+        assert_eq!(decoded[1].as_ref().unwrap().0.id(), "order-1");
+    }
+
+    #[tokio::test]
+    async fn signed_events_verify_after_a_round_trip() {
+        let cluster = MockCluster::new(1).unwrap();
+        cluster.create_topic("orders", 1, 1).unwrap();
+
+        let broker = KafkaBroker::builder()
+            .with_bootstrap_servers(vec![cluster.bootstrap_servers()])
+            .with_auto_offset_reset(OffsetReset::Earliest)
+            .build()
+            .await
+            .unwrap();
+        let key = SigningKey::hmac_sha256(b"secret".to_vec());
+        let publisher = SigningPublisher::new(
+            broker
+                .publisher(PublisherOptions::builder().channel("orders").build())
+                .await
+                .unwrap(),
+            key.clone(),
+        );
+        let consumer = broker
+            .consumer(ConsumerOptions::builder().channel("orders").consumer_tag("test").build())
+            .await
+            .unwrap();
+
+        publisher
+            .publish_event(
+                EventBuilder::new()
+                    .source("test")
+                    .subject("order-1")
+                    .build_raw_with_content_type("order.placed", vec![0x08, 0x01], "application/x-protobuf")
+                    .unwrap()
+            )
+            .await
+            .unwrap();
+
+        let envelope = tokio::time::timeout(Duration::from_secs(10), consumer.stream_events().await.unwrap().next())
+            .await
+            .expect("the event was not received")
+            .unwrap()
+            .unwrap();
+
+        key.verifying_key()
+            .verify(envelope.event())
+            .unwrap();
     }
 }
@@ -0,0 +1,64 @@
+use std::sync::Mutex;
+use futures::channel::mpsc::{UnboundedReceiver, UnboundedSender, unbounded};
+use rdkafka::{
+    ClientContext,
+    TopicPartitionList,
+    consumer::{BaseConsumer, ConsumerContext, Rebalance},
+};
+
+use enroute_core::consumer::{ConsumerEvent, Partition};
+
+
+fn partitions(list: &TopicPartitionList) -> Vec<Partition> {
+    list.elements()
+        .iter()
+        .map(|e| Partition {
+            channel: e.topic().to_string(),
+            partition: e.partition(),
+        })
+        .collect()
+}
+
+/// The context of the consumers created by a
+/// [`KafkaBroker`](crate::broker::KafkaBroker), forwarding rebalances to the control
+/// stream of the consumer, if one is open.
+#[derive(Default)]
+pub struct KafkaConsumerContext {
+    control: Mutex<Option<UnboundedSender<ConsumerEvent>>>,
+}
+
+impl KafkaConsumerContext {
+    /// Forward the rebalances from now on to the returned receiver, replacing any
+    /// receiver subscribed before.
+    pub(crate) fn subscribe(&self) -> UnboundedReceiver<ConsumerEvent> {
+        let (tx, rx) = unbounded();
+
+        *self.control
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(tx);
+
+        rx
+    }
+}
+
+impl ClientContext for KafkaConsumerContext {}
+
+impl ConsumerContext for KafkaConsumerContext {
+    fn post_rebalance(&self, _base_consumer: &BaseConsumer<Self>, rebalance: &Rebalance<'_>) {
+        let event = match rebalance {
+            Rebalance::Assign(list) => ConsumerEvent::Assigned(partitions(list)),
+            Rebalance::Revoke(list) => ConsumerEvent::Revoked(partitions(list)),
+            Rebalance::Error(_) => return,
+        };
+        let mut control = self.control
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        if control
+            .as_ref()
+            .is_some_and(|tx| tx.unbounded_send(event).is_err())
+        {
+            *control = None;
+        }
+    }
+}
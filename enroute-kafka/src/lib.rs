@@ -4,9 +4,19 @@ extern crate self as enroute_kafka;
 pub mod publisher;
 pub mod consumer;
 pub mod broker;
+pub mod transaction;
+pub mod acker;
+pub mod admin;
+pub mod context;
+pub mod reconnect;
 
 pub use crate::{
-    broker::{KafkaBroker, KafkaBrokerBuilder, KafkaBrokerConfig},
-    consumer::KafkaConsumer,
-    publisher::KafkaPublisher,
+    broker::{KafkaBroker, KafkaBrokerBuilder, KafkaBrokerConfig, OffsetReset},
+    consumer::{KafkaConsumer, ConsumerLag, PartitionLag, TOMBSTONE_EVENT_TYPE},
+    publisher::{KafkaPublisher, PublishOutcome, TimestampSource, DEFAULT_HEADER_PREFIX},
+    transaction::{TransactionalPublisher, Transaction},
+    acker::KafkaAcker,
+    admin::KafkaAdmin,
+    context::KafkaConsumerContext,
+    reconnect::ReconnectingConsumer,
 };
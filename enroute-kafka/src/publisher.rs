@@ -1,23 +1,71 @@
-use std::time::Duration;
-use chrono::Utc;
+use std::{sync::Arc, time::Duration, collections::HashMap, future::Future};
 use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
 use rdkafka::{
+    error::KafkaError,
     producer::{FutureProducer, FutureRecord},
     message::{OwnedHeaders, Header},
+    types::RDKafkaErrorCode,
 };
 
 use enroute_core::{
-    event::Event,
+    event::{Event, ExtensionValue, extension_value_to_string},
+    clock::{Clock, SystemClock},
     error::{Error, Result},
-    publisher::Publisher,
+    publisher::{Publisher, NativePublisher},
 };
 
 
+/// The default prefix of the headers carrying CloudEvent attributes.
+pub const DEFAULT_HEADER_PREFIX: &str = "ce-";
+
+/// The source of the timestamp set on published Kafka records.
+///
+/// This only controls the timestamp sent by the producer. On topics with
+/// `message.timestamp.type=LogAppendTime` the broker overwrites it with the append time
+/// either way, while on topics with `CreateTime` (the default) the producer's timestamp
+/// is kept.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimestampSource {
+    /// Use the event's time, falling back to the current time for events without one.
+    #[default]
+    EventTime,
+    /// Leave the timestamp unset, so it is stamped by the producer when sent or, on
+    /// `LogAppendTime` topics, by the broker when appended. The event's time is still
+    /// sent in its time header, which consumers read it back from.
+    BrokerTime,
+}
+
+
+/// The outcome of [`KafkaPublisher::try_publish`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublishOutcome {
+    /// The record was delivered.
+    Sent,
+    /// The producer's queue was full, so the record was not sent.
+    QueueFull,
+}
+
+
+type KeyFn = Arc<dyn Fn(&Event) -> Option<String> + Send + Sync>;
+
+/// A Kafka publisher sending events in binary mode, with the event data as the record
+/// payload and the attributes and extensions as headers.
+/// 
+/// Extensions are written as headers under their own name, so an event's idempotency
+/// key is carried in an `idempotencykey` header for consumers and dedup layers.
 #[derive(Clone)]
 pub struct KafkaPublisher {
     producer: FutureProducer,
     topic: String,
     timeout: Duration,
+    default_extensions: HashMap<String, ExtensionValue>,
+    strict_channel_check: bool,
+    timestamp_source: TimestampSource,
+    header_prefix: String,
+    clock: Arc<dyn Clock>,
+    key_fn: Option<KeyFn>,
 }
 
 impl KafkaPublisher {
@@ -26,53 +74,239 @@ impl KafkaPublisher {
             producer,
             topic,
             timeout: timeout.unwrap_or_else(|| Duration::from_secs(0)),
+            default_extensions: HashMap::new(),
+            strict_channel_check: false,
+            timestamp_source: TimestampSource::default(),
+            header_prefix: DEFAULT_HEADER_PREFIX.to_string(),
+            clock: Arc::new(SystemClock),
+            key_fn: None,
         }
     }
 
+    /// Set the source of the timestamp set on published records.
+    pub fn with_timestamp_source(mut self, source: TimestampSource) -> Self {
+        self.timestamp_source = source;
+        self
+    }
+
+    /// Set the prefix of the headers carrying CloudEvent attributes, `ce-` by default.
+    /// 
+    /// Consumers of the topic must be configured with the same prefix.
+    pub fn with_header_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.header_prefix = prefix.into();
+        self
+    }
+
+    /// Set the clock used to timestamp records of events without a time.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Set a function computing the record key of each event, to control partitioning.
+    /// 
+    /// The key falls back to the event id when the function returns `None`, as it is
+    /// when no function is set.
+    pub fn with_key_fn<F>(mut self, key_fn: F) -> Self
+    where
+        F: Fn(&Event) -> Option<String> + Send + Sync + 'static,
+    {
+        self.key_fn = Some(Arc::new(key_fn));
+        self
+    }
+
+    /// Set whether [`Publisher::publish_typed`] rejects event data of another channel.
+    pub fn with_strict_channel_check(mut self, strict: bool) -> Self {
+        self.strict_channel_check = strict;
+        self
+    }
+
+    /// Set extensions applied to every published event that doesn't already carry them.
+    pub fn with_default_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.default_extensions = extensions;
+        self
+    }
+
+    /// Returns a reference to the underlying producer.
+    pub fn producer(&self) -> &FutureProducer {
+        &self.producer
+    }
+
     pub async fn publish(&self, event: Event) -> Result<()> {
-        let event_id = event.id().to_string();
+        self.publish_to(&self.topic, event).await
+    }
+
+    /// Publish an event to the given topic instead of the publisher's own topic, using
+    /// the same producer.
+    pub async fn publish_to(&self, topic: &str, event: Event) -> Result<()> {
+        self.send(topic, event, None, true)
+            .await
+            .map(|_| ())
+    }
+
+    /// Publish an event to the given partition of the publisher's topic.
+    /// 
+    /// This bypasses the partitioner, so the record key no longer decides the partition,
+    /// allowing sharding schemes that don't map onto key hashing.
+    /// 
+    /// # Arguments
+    /// * `event` - The event to publish.
+    /// * `partition` - The partition to publish the event to.
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn publish_to_partition(&self, event: Event, partition: i32) -> Result<()> {
+        self.send(&self.topic, event, Some(partition), true)
+            .await
+            .map(|_| ())
+    }
+
+    /// Publish an event without waiting for room in the producer's queue.
+    /// 
+    /// Unlike [`KafkaPublisher::publish`], which retries while the queue is full until its
+    /// timeout expires, this returns [`PublishOutcome::QueueFull`] right away, so callers
+    /// can apply their own backpressure or shed load.
+    /// 
+    /// # Arguments
+    /// * `event` - The event to publish.
+    /// 
+    /// # Returns
+    /// A result containing the outcome once the record is delivered or rejected, or an
+    /// error.
+    pub async fn try_publish(&self, event: Event) -> Result<PublishOutcome> {
+        self.send(&self.topic, event, None, false).await
+    }
+
+    async fn send(&self, topic: &str, mut event: Event, partition: Option<i32>, wait_for_queue: bool) -> Result<PublishOutcome> {
+        event.apply_default_extensions(&self.default_extensions);
+
+        let key = self.key_fn
+            .as_ref()
+            .and_then(|key_fn| key_fn(&event))
+            .unwrap_or_else(|| event.id().to_string());
         let payload = event.data_as_bytes()?;
-        let record = FutureRecord::<'_, String, Vec<u8>>::to(&self.topic)
-            .key(&event_id)
-            .timestamp(
-                event
-                    .time()
-                    .map(|t| t.timestamp_millis())
-                    .unwrap_or_else(|| Utc::now().timestamp_millis())
-            )
+        let extensions = event
+            .extensions()
+            .into_iter()
+            .map(|(k, v)| (k, extension_value_to_string(&v)))
+            .collect::<Vec<_>>();
+        let headers = extensions
+            .iter()
+            .fold(
+                OwnedHeaders::new(),
+                |headers, (key, value)| headers.insert(Header {
+                    key,
+                    value: Some(value.as_str()),
+                }),
+            );
+        let mut record = FutureRecord::<'_, String, Vec<u8>>::to(topic)
+            .key(&key)
             .headers(
-                OwnedHeaders::new()
+                headers
                     .insert(Header {
-                        key: "ce-specversion",
+                        key: &format!("{}specversion", self.header_prefix),
                         value: Some(event.specversion().as_str()),
                     })
                     .insert(Header {
-                        key: "ce-type",
+                        key: &format!("{}type", self.header_prefix),
                         value: Some(event.type_()),
                     })
                     .insert(Header {
-                        key: "ce-source",
+                        key: &format!("{}source", self.header_prefix),
                         value: Some(event.source()),
                     })
                     .insert(Header {
-                        key: "ce-id",
+                        key: &format!("{}id", self.header_prefix),
                         value: Some(event.id()),
                     })
                     .insert(Header {
-                        key: "ce-time",
+                        key: &format!("{}subject", self.header_prefix),
+                        value: event.subject(),
+                    })
+                    .insert(Header {
+                        key: &format!("{}time", self.header_prefix),
                         value: event.time().map(|t| t.to_rfc3339()).as_ref(),
                     })
                     .insert(Header {
-                        key: "ce-dataschema",
+                        key: &format!("{}dataschema", self.header_prefix),
                         value: event.dataschema().map(|url| url.as_str().to_string()).as_deref(),
                     })
                     .insert(Header {
-                        key: "ce-datacontenttype",
+                        key: &format!("{}datacontenttype", self.header_prefix),
                         value: event.datacontenttype(),
                     }),
             )
             .payload(&payload);
 
+        if let Some(partition) = partition {
+            record = record.partition(partition);
+        }
+
+        if self.timestamp_source == TimestampSource::EventTime {
+            record = record.timestamp(
+                event
+                    .time()
+                    .map(|t| t.timestamp_millis())
+                    .unwrap_or_else(|| self.clock.now().timestamp_millis())
+            );
+        }
+
+        if wait_for_queue {
+            self.producer
+                .send(record, self.timeout)
+                .await
+                .map_err(|(e, _)| Error::Publisher(e.to_string()))?;
+
+            return Ok(PublishOutcome::Sent);
+        }
+
+        let delivery = match self.producer.send_result(record) {
+            Ok(delivery) => delivery,
+            Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), _)) => {
+                return Ok(PublishOutcome::QueueFull);
+            }
+            Err((e, _)) => return Err(Error::Publisher(e.to_string())),
+        };
+
+        delivery
+            .await
+            .map_err(|e| Error::Publisher(e.to_string()))?
+            .map_err(|(e, _)| Error::Publisher(e.to_string()))?;
+
+        Ok(PublishOutcome::Sent)
+    }
+
+    /// Publish a plain record to the publisher's topic, without CloudEvent headers.
+    /// 
+    /// This uses the publisher's producer for traffic that isn't CloudEvents, such as
+    /// bridging to legacy topics. Default extensions and the timestamp source don't apply,
+    /// so the record is stamped by the producer when sent.
+    /// 
+    /// # Arguments
+    /// * `key` - The optional record key.
+    /// * `payload` - The record payload.
+    /// * `headers` - The record headers.
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn publish_raw(&self, key: Option<&str>, payload: Vec<u8>, headers: HashMap<String, String>) -> Result<()> {
+        let headers = headers
+            .iter()
+            .fold(
+                OwnedHeaders::new(),
+                |headers, (key, value)| headers.insert(Header {
+                    key,
+                    value: Some(value.as_str()),
+                }),
+            );
+        let mut record = FutureRecord::<'_, str, Vec<u8>>::to(&self.topic)
+            .headers(headers)
+            .payload(&payload);
+
+        if let Some(key) = key {
+            record = record.key(key);
+        }
+
         self.producer
             .send(record, self.timeout)
             .await
@@ -88,4 +322,22 @@ impl Publisher for KafkaPublisher {
     async fn publish_event(&self, event: Event) -> Result<()> {
         self.publish(event).await
     }
+
+    async fn publish_event_to(&self, channel: &str, event: Event) -> Result<()> {
+        self.publish_to(channel, event).await
+    }
+
+    fn channel(&self) -> &str {
+        &self.topic
+    }
+
+    fn strict_channel_check(&self) -> bool {
+        self.strict_channel_check
+    }
+}
+
+impl NativePublisher for KafkaPublisher {
+    fn publish(&self, event: Event) -> impl Future<Output = Result<()>> + Send {
+        KafkaPublisher::publish(self, event)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,197 @@
+use std::{
+    mem,
+    sync::{Arc, Mutex},
+    pin::Pin,
+    time::Duration,
+};
+use async_trait::async_trait;
+use async_stream::stream;
+use futures::{Stream, StreamExt, stream};
+use rdkafka::util::{AsyncRuntime, DefaultRuntime};
+
+use enroute_core::{
+    broker::Broker,
+    consumer::{Consumer, ConsumerOptions},
+    envelope::Envelope,
+    error::{Error, Result},
+};
+
+use crate::{broker::KafkaBroker, consumer::KafkaConsumer};
+
+
+/// The default delay before the first reconnection attempt.
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+/// The default maximum delay between reconnection attempts.
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+type EnvelopeStream = Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>;
+type CurrentConsumer = Arc<Mutex<Option<Arc<KafkaConsumer>>>>;
+
+/// Create a consumer and start streaming its events, keeping it as the current consumer.
+async fn connect(broker: &KafkaBroker, options: &ConsumerOptions, current: &CurrentConsumer) -> Result<EnvelopeStream> {
+    let consumer = Arc::new(broker.consumer(options.clone()).await?);
+    let events = consumer.stream_events().await?;
+
+    *current.lock().unwrap_or_else(|e| e.into_inner()) = Some(consumer);
+
+    Ok(events)
+}
+
+/// Returns whether the current consumer's connection failed.
+fn connection_failed(current: &CurrentConsumer) -> bool {
+    current
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .is_some_and(|consumer| consumer.connection_failed())
+}
+
+/// A Kafka consumer that recreates its underlying consumer when the connection fails,
+/// resuming the stream transparently.
+///
+/// On a fatal error or a transport failure of the Kafka client, e.g. when all brokers
+/// are down or can't be resolved, the consumer is dropped and created again with the
+/// same options, so it subscribes again to the same topic with the same group, waiting
+/// with exponential backoff between attempts. Other errors, such as other
+/// [`Error::Consumer`] errors of the client or [`Error::Deserialization`] for malformed
+/// messages, are yielded as is without reconnecting.
+///
+/// Messages yielded but not committed before a reconnection are delivered again by the
+/// new consumer. Pausing applies to the current consumer only, so a reconnection resumes
+/// fetching.
+pub struct ReconnectingConsumer {
+    broker: KafkaBroker,
+    options: ConsumerOptions,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_attempts: Option<u32>,
+    current: CurrentConsumer,
+}
+
+impl ReconnectingConsumer {
+    /// Create a new [`ReconnectingConsumer`].
+    ///
+    /// # Arguments
+    /// * `broker` - The broker to create consumers with.
+    /// * `options` - The options of the consumers.
+    ///
+    /// # Returns
+    /// The new [`ReconnectingConsumer`].
+    pub fn new(broker: KafkaBroker, options: ConsumerOptions) -> Self {
+        Self {
+            broker,
+            options,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            max_attempts: None,
+            current: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Set the delay before the first reconnection attempt, doubled after each failed
+    /// attempt up to the maximum delay. Defaults to 100 milliseconds and 30 seconds.
+    ///
+    /// The delay is reset once the new consumer yields a message.
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Set the number of consecutive failed reconnection attempts after which the stream
+    /// yields the last error and ends. By default reconnection is attempted forever.
+    pub fn with_max_attempts(mut self, max_attempts: Option<u32>) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Returns the options the consumers are created with.
+    pub fn options(&self) -> &ConsumerOptions {
+        &self.options
+    }
+
+    /// Returns the current underlying consumer, if streaming has started and the consumer
+    /// is not reconnecting.
+    pub fn current(&self) -> Option<Arc<KafkaConsumer>> {
+        self.current
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+#[async_trait]
+impl Consumer for ReconnectingConsumer {
+    /// Stream incoming events, reconnecting on connection failures. Creating the first
+    /// consumer is not retried, so configuration errors are returned right away.
+    async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
+        let broker = self.broker.clone();
+        let options = self.options.clone();
+        let current = self.current.clone();
+        let initial_backoff = self.initial_backoff;
+        let max_backoff = self.max_backoff;
+        let max_attempts = self.max_attempts;
+        let mut events = connect(&broker, &options, &current).await?;
+
+        Ok(Box::pin(stream! {
+            let mut backoff = initial_backoff;
+
+            while let Some(envelope) = events.next().await {
+                match envelope {
+                    Ok(envelope) => {
+                        backoff = initial_backoff;
+                        yield Ok(envelope);
+                    }
+                    Err(Error::Consumer(_)) if connection_failed(&current) => {
+                        // Drop the failed consumer before creating its replacement, so it
+                        // leaves the group.
+                        drop(mem::replace(&mut events, Box::pin(stream::empty())));
+                        current.lock().unwrap_or_else(|e| e.into_inner()).take();
+
+                        let mut attempts = 0;
+
+                        loop {
+                            DefaultRuntime::delay_for(backoff).await;
+                            backoff = (backoff * 2).min(max_backoff);
+                            attempts += 1;
+
+                            match connect(&broker, &options, &current).await {
+                                Ok(reconnected) => {
+                                    events = reconnected;
+                                    break;
+                                }
+                                Err(e) if max_attempts.is_some_and(|max| attempts >= max) => {
+                                    yield Err(e);
+                                    return;
+                                }
+                                Err(_) => {}
+                            }
+                        }
+                    }
+                    Err(e) => yield Err(e),
+                }
+            }
+        }))
+    }
+
+    async fn pause(&self) -> Result<()> {
+        match self.current() {
+            Some(consumer) => consumer.pause().await,
+            None => Ok(()),
+        }
+    }
+
+    async fn resume(&self) -> Result<()> {
+        match self.current() {
+            Some(consumer) => consumer.resume().await,
+            None => Ok(()),
+        }
+    }
+
+    async fn close(&self) -> Result<()> {
+        match self.current() {
+            Some(consumer) => consumer.close().await,
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use std::{time::Duration, future::Future};
+use rdkafka::{
+    TopicPartitionList,
+    consumer::ConsumerGroupMetadata,
+    error::{KafkaError, KafkaResult},
+    producer::{FutureProducer, Producer},
+};
+
+use enroute_core::{
+    event::Event,
+    error::{Error, Result},
+};
+
+use crate::{consumer::run_blocking, publisher::KafkaPublisher};
+
+
+fn transaction_error(e: KafkaError) -> Error {
+    Error::Publisher(e.to_string())
+}
+
+/// Run a blocking transaction control call of a producer on the blocking thread pool,
+/// so waiting on the transaction coordinator doesn't stall the executor.
+async fn control<T, F>(producer: &FutureProducer, f: F) -> Result<KafkaResult<T>>
+where
+    F: FnOnce(&FutureProducer) -> KafkaResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let producer = producer.clone();
+
+    run_blocking(move || Ok(f(&producer))).await
+}
+
+/// Associate consumed offsets with the current transaction of a producer.
+async fn send_offsets(
+    producer: &FutureProducer,
+    offsets: &TopicPartitionList,
+    group: ConsumerGroupMetadata,
+    timeout: Duration,
+) -> Result<()> {
+    let offsets = offsets.clone();
+
+    control(producer, move |producer| producer.send_offsets_to_transaction(&offsets, &group, timeout))
+        .await?
+        .map_err(transaction_error)
+}
+
+/// A handle to an open Kafka transaction, passed to the closure given to
+/// [`TransactionalPublisher::transaction`].
+#[derive(Clone)]
+pub struct Transaction {
+    publisher: KafkaPublisher,
+    timeout: Duration,
+}
+
+impl Transaction {
+    /// Publish an event as part of the transaction.
+    ///
+    /// # Arguments
+    /// * `event` - The event to be published.
+    ///
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn publish_event(&self, event: Event) -> Result<()> {
+        self.publisher.publish(event).await
+    }
+
+    /// Associate consumed offsets with the transaction, so they are committed
+    /// atomically with the published events.
+    ///
+    /// # Arguments
+    /// * `offsets` - The offsets to commit, typically the consumer's position.
+    /// * `group` - The metadata of the consumer group the offsets belong to, as returned
+    ///   by [`KafkaConsumer::group_metadata`](crate::consumer::KafkaConsumer::group_metadata).
+    ///
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn send_offsets(&self, offsets: &TopicPartitionList, group: ConsumerGroupMetadata) -> Result<()> {
+        send_offsets(self.publisher.producer(), offsets, group, self.timeout).await
+    }
+}
+
+
+/// A Kafka publisher that publishes events within transactions, for exactly-once
+/// read-process-write pipelines.
+///
+/// The underlying producer must be configured with a `transactional.id`. Transaction
+/// control calls wait on the transaction coordinator for up to the configured timeout,
+/// on the blocking thread pool.
+#[derive(Clone)]
+pub struct TransactionalPublisher {
+    publisher: KafkaPublisher,
+    timeout: Duration,
+}
+
+impl TransactionalPublisher {
+    /// Create a new [`TransactionalPublisher`], initializing transactions on the producer.
+    ///
+    /// # Arguments
+    /// * `publisher` - A publisher whose producer has a `transactional.id` set.
+    /// * `timeout` - The timeout for transaction control operations.
+    ///
+    /// # Returns
+    /// A result containing the transactional publisher or an error.
+    pub async fn new(publisher: KafkaPublisher, timeout: Duration) -> Result<Self> {
+        control(publisher.producer(), move |producer| producer.init_transactions(timeout))
+            .await?
+            .map_err(transaction_error)?;
+
+        Ok(Self { publisher, timeout })
+    }
+
+    /// Returns a reference to the underlying [`KafkaPublisher`].
+    pub fn publisher(&self) -> &KafkaPublisher {
+        &self.publisher
+    }
+
+    /// Begin a new transaction.
+    pub async fn begin_transaction(&self) -> Result<()> {
+        control(self.publisher.producer(), |producer| producer.begin_transaction())
+            .await?
+            .map_err(transaction_error)
+    }
+
+    /// Associate consumed offsets with the current transaction.
+    ///
+    /// # Arguments
+    /// * `offsets` - The offsets to commit, typically the consumer's position.
+    /// * `group` - The metadata of the consumer group the offsets belong to, as returned
+    ///   by [`KafkaConsumer::group_metadata`](crate::consumer::KafkaConsumer::group_metadata).
+    pub async fn send_offsets_to_transaction(&self, offsets: &TopicPartitionList, group: ConsumerGroupMetadata) -> Result<()> {
+        send_offsets(self.publisher.producer(), offsets, group, self.timeout).await
+    }
+
+    /// Commit the current transaction.
+    pub async fn commit_transaction(&self) -> Result<()> {
+        let timeout = self.timeout;
+
+        control(self.publisher.producer(), move |producer| producer.commit_transaction(timeout))
+            .await?
+            .map_err(transaction_error)
+    }
+
+    /// Abort the current transaction.
+    pub async fn abort_transaction(&self) -> Result<()> {
+        let timeout = self.timeout;
+
+        control(self.publisher.producer(), move |producer| producer.abort_transaction(timeout))
+            .await?
+            .map_err(transaction_error)
+    }
+
+    /// Run the given closure within a transaction.
+    ///
+    /// The transaction is committed if the closure succeeds, and aborted if the closure
+    /// fails or the commit fails with an abortable error.
+    ///
+    /// # Arguments
+    /// * `f` - The closure to run, given a handle to publish events and send offsets.
+    ///
+    /// # Returns
+    /// A result containing the closure's output or an error.
+    pub async fn transaction<F, Fut, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(Transaction) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.begin_transaction().await?;
+
+        let output = match f(Transaction {
+            publisher: self.publisher.clone(),
+            timeout: self.timeout,
+        }).await {
+            Ok(output) => output,
+            Err(e) => {
+                self.abort_transaction().await?;
+                return Err(e);
+            }
+        };
+
+        let timeout = self.timeout;
+        let committed = control(self.publisher.producer(), move |producer| producer.commit_transaction(timeout))
+            .await?;
+
+        match committed {
+            Ok(()) => Ok(output),
+            Err(KafkaError::Transaction(e)) if e.txn_requires_abort() => {
+                self.abort_transaction().await?;
+                Err(Error::Publisher(e.to_string()))
+            }
+            Err(e) => Err(transaction_error(e)),
+        }
+    }
+}
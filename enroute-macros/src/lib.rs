@@ -2,7 +2,7 @@
 extern crate self as enroute_macros;
 
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{quote, ToTokens};
 use syn::{parse_macro_input, DeriveInput, Expr};
 use syn::punctuated::Punctuated;
 use syn::parse::{Parse, ParseStream};
@@ -12,17 +12,15 @@ use syn::Error;
 struct EventDataMacroArgs {
     event_type: Expr,
     channel_name: Expr,
+    subject_field: Option<syn::Ident>,
 }
 
 impl EventDataMacroArgs {
-    fn from_attributes(attrs: &[syn::Attribute]) -> Option<EventDataMacroArgs> {
-        for attr in attrs.iter().filter(|a| a.path().is_ident("event_data")) {
-            if let Ok(args) = attr.parse_args::<EventDataMacroArgs>() {
-                return Some(args);
-            }
+    fn from_attributes(input: &DeriveInput) -> Result<EventDataMacroArgs, Error> {
+        match input.attrs.iter().find(|a| a.path().is_ident("event_data")) {
+            Some(attr) => attr.parse_args::<EventDataMacroArgs>(),
+            None => Err(Error::new_spanned(&input.ident, "Missing event_data attribute")),
         }
-
-        None
     }
 }
 
@@ -30,6 +28,7 @@ impl Parse for EventDataMacroArgs {
     fn parse(input: ParseStream) -> Result<Self, Error> {
         let mut event_type = None;
         let mut channel_name = None;
+        let mut subject_field = None;
 
         let args = Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated(input)?;
     
@@ -39,6 +38,13 @@ impl Parse for EventDataMacroArgs {
                     event_type = Some(nv.value);
                 } else if nv.path.is_ident("channel_name") {
                     channel_name = Some(nv.value);
+                } else if nv.path.is_ident("subject_field") {
+                    match nv.value {
+                        Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(field), .. }) => {
+                            subject_field = Some(field.parse::<syn::Ident>()?);
+                        }
+                        value => return Err(Error::new_spanned(value, "subject_field must be a string literal")),
+                    }
                 }
             }
         }
@@ -46,6 +52,7 @@ impl Parse for EventDataMacroArgs {
         Ok(EventDataMacroArgs {
             event_type: event_type.ok_or_else(|| Error::new(input.span(), "Missing event_type argument"))?,
             channel_name: channel_name.ok_or_else(|| Error::new(input.span(), "Missing channel_name argument"))?,
+            subject_field,
         })
     }
 }
@@ -55,9 +62,17 @@ pub fn derive_event_data(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
 
-    let args = EventDataMacroArgs::from_attributes(&input.attrs).expect("Missing event_data attribute");
+    let args = match EventDataMacroArgs::from_attributes(&input) {
+        Ok(args) => args,
+        Err(e) => return e.to_compile_error().into(),
+    };
     let event_type = args.event_type;
     let channel_name = args.channel_name;
+    let subject = args.subject_field.map(|field| quote! {
+        fn subject(&self) -> Option<String> {
+            Some(self.#field.to_string())
+        }
+    });
 
     let expanded = quote! {
         impl enroute::EventData for #name {
@@ -68,6 +83,158 @@ pub fn derive_event_data(input: TokenStream) -> TokenStream {
             fn channel_name() -> &'static str {
                 #channel_name
             }
+
+            #subject
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+struct DispatchArm {
+    /// The event data type of the arm, or `None` for the `_` fallback arm.
+    ty: Option<syn::Type>,
+    handler: Expr,
+}
+
+impl Parse for DispatchArm {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let ty = if input.peek(syn::Token![_]) {
+            input.parse::<syn::Token![_]>()?;
+            None
+        } else {
+            Some(input.parse::<syn::Type>()?)
+        };
+
+        input.parse::<syn::Token![=>]>()?;
+
+        Ok(DispatchArm {
+            ty,
+            handler: input.parse()?,
+        })
+    }
+}
+
+struct DispatchEventsArgs {
+    envelope: Expr,
+    arms: Vec<DispatchArm>,
+    fallback: Option<Expr>,
+}
+
+impl Parse for DispatchEventsArgs {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let envelope = input.parse::<Expr>()?;
+        input.parse::<syn::Token![,]>()?;
+
+        let content;
+        syn::braced!(content in input);
+        let _ = input.parse::<Option<syn::Token![,]>>()?;
+
+        let mut arms = Vec::new();
+        let mut fallback = None;
+
+        for arm in Punctuated::<DispatchArm, syn::Token![,]>::parse_terminated(&content)? {
+            match arm.ty {
+                Some(_) if fallback.is_some() => {
+                    return Err(Error::new_spanned(arm.handler, "the _ arm must be the last arm"));
+                }
+                Some(_) => arms.push(arm),
+                None if fallback.is_some() => {
+                    return Err(Error::new_spanned(arm.handler, "duplicate _ arm"));
+                }
+                None => fallback = Some(arm.handler),
+            }
+        }
+
+        Ok(DispatchEventsArgs {
+            envelope,
+            arms,
+            fallback,
+        })
+    }
+}
+
+/// Expands to the awaited result of calling a handler with a value. Closures are
+/// inlined, so their body may borrow their argument, as in `|data| async { ... }`.
+fn call_handler(handler: &Expr, value: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    match handler {
+        Expr::Closure(closure) if closure.inputs.len() == 1 => {
+            let pat = &closure.inputs[0];
+            let body = &closure.body;
+
+            match closure.asyncness {
+                Some(_) => quote! {
+                    async move {
+                        let #pat = #value;
+                        #body
+                    }.await
+                },
+                None => quote! {
+                    {
+                        let #pat = #value;
+                        (#body).await
+                    }
+                },
+            }
+        }
+        handler => quote! {
+            (#handler)(#value).await
+        },
+    }
+}
+
+/// Dispatch an envelope to the handler of its event type, then settle it.
+///
+/// Takes an envelope and a braced set of `Type => handler` arms, where `Type` implements
+/// `EventData` and the handler is a closure or function taking the deserialized data and
+/// returning a future of `enroute::Result<()>`, e.g. `|data: UserCreated| async { ... }`.
+/// The arm matching the event's type by `EventData::event_type` is called, and the
+/// envelope is acked if the handler succeeds and nacked if it fails or the data cannot be
+/// deserialized. An optional last `_ => handler` arm is called with a clone of the event
+/// for the other types. Without it, the envelopes of unregistered types are acked and
+/// skipped, so a consumer sharing a channel with other event types doesn't have them
+/// redelivered or dead-lettered.
+///
+/// Expands to an expression awaiting the handler, evaluating to the `enroute::Result<()>`
+/// of the dispatch.
+#[proc_macro]
+pub fn dispatch_events(input: TokenStream) -> TokenStream {
+    let DispatchEventsArgs { envelope, arms, fallback } = parse_macro_input!(input as DispatchEventsArgs);
+
+    let branches = arms.iter().map(|arm| {
+        let ty = arm.ty.as_ref().map(ToTokens::to_token_stream);
+        let call = call_handler(&arm.handler, quote!(__data));
+
+        quote! {
+            if __event.type_() == <#ty as enroute::EventData>::event_type() {
+                match __event.data::<#ty>() {
+                    Ok(__data) => #call,
+                    Err(e) => Err(e),
+                }
+            } else
+        }
+    });
+    let fallback = match fallback {
+        Some(handler) => call_handler(&handler, quote!(__event.clone())),
+        None => quote! {
+            Ok(())
+        },
+    };
+
+    let expanded = quote! {
+        {
+            let __envelope = #envelope;
+            let __event = __envelope.event();
+            let __result: enroute::Result<()> = #(#branches)* {
+                #fallback
+            };
+
+            match &__result {
+                Ok(_) => __envelope.ack().await,
+                Err(_) => __envelope.nack().await,
+            }
+
+            __result
         }
     };
 
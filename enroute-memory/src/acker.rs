@@ -4,7 +4,10 @@ use std::{
 };
 use async_trait::async_trait;
 
-use enroute_core::{event::Event, envelope::Acker};
+use enroute_core::{
+    event::Event,
+    envelope::{Acker, DELIVERY_COUNT_EXTENSION, delivery_count},
+};
 
 use crate::inner::BrokerInner;
 
@@ -15,7 +18,7 @@ pub struct InMemoryAcker {
     channel: String,
     event: Event,
     done: Arc<AtomicBool>,
-    requeue: bool,
+    requeue: Option<bool>,
 }
 
 impl InMemoryAcker {
@@ -23,7 +26,7 @@ impl InMemoryAcker {
         broker_inner: Weak<BrokerInner>,
         channel: String,
         event: Event,
-        requeue: bool,
+        requeue: Option<bool>,
     ) -> Self {
         Self {
             broker_inner,
@@ -48,10 +51,13 @@ impl Acker for InMemoryAcker {
             return;
         }
 
-        if self.requeue {
-            if let Some(inner) = self.broker_inner.upgrade() {
+        if let Some(inner) = self.broker_inner.upgrade() {
+            if self.requeue.unwrap_or_else(|| inner.requeue_on_nack()) {
+                let mut event = self.event.clone();
+                event.0.set_extension(DELIVERY_COUNT_EXTENSION, i64::from(delivery_count(&event)) + 1);
+
                 let _ = inner
-                    .publish(&self.channel, &self.event)
+                    .publish(&self.channel, &event)
                     .await;
             }
         }
@@ -0,0 +1,38 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+
+use enroute_core::{
+    admin::Admin,
+    error::Result,
+};
+
+use crate::inner::BrokerInner;
+
+
+/// An admin managing the channels of an [`InMemoryBroker`](crate::broker::InMemoryBroker),
+/// which are its topics.
+#[derive(Clone)]
+pub struct InMemoryAdmin {
+    pub(crate) inner: Arc<BrokerInner>,
+}
+
+#[async_trait]
+impl Admin for InMemoryAdmin {
+    /// Create a channel, so it is listed before any consumer registers with it. Creating
+    /// an existing channel has no effect.
+    async fn create_topic(&self, topic: &str) -> Result<()> {
+        self.inner.create_channel(topic).await;
+        Ok(())
+    }
+
+    /// Delete a channel, ending the event streams of its consumers (see
+    /// [`InMemoryBroker::close_channel`](crate::broker::InMemoryBroker::close_channel)).
+    async fn delete_topic(&self, topic: &str) -> Result<()> {
+        self.inner.close_channel(topic).await;
+        Ok(())
+    }
+
+    async fn list_topics(&self) -> Result<Vec<String>> {
+        Ok(self.inner.channels().await)
+    }
+}
@@ -1,34 +1,83 @@
-use std::{sync::Arc, fmt::Debug};
+use std::{collections::HashMap, sync::Arc, fmt::Debug, time::Duration};
 use async_trait::async_trait;
 use serde::{Serialize, Deserialize};
+use futures_timer::Delay;
+use futures_timeout::TimeoutExt;
 
 use enroute_core::{
     broker::{Broker, BrokerBuilder},
-    consumer::ConsumerOptions,
+    consumer::{ConsumerOptions, DeliveryGuarantee},
+    pause::PauseGate,
     publisher::PublisherOptions,
-    error::Result,
+    event::Event,
+    error::{Error, Result},
 };
 
 use crate::{
     inner::BrokerInner,
+    admin::InMemoryAdmin,
     publisher::InMemoryPublisher,
     consumer::InMemoryConsumer,
 };
 
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How events are assigned to the consumers sharing a consumer tag.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsumerAssignment {
+    /// Assign events to consumers in turn, in registration order.
+    #[default]
+    RoundRobin,
+    /// Assign events by the hash of their `partitionkey` extension, falling back to their
+    /// subject, so events with the same key always go to the same consumer as long as
+    /// the group's consumers don't change. Events with neither are assigned round-robin.
+    KeyHash,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct InMemoryBrokerConfig {
+    #[serde(default)]
     requeue_on_nack: bool,
+    #[serde(default)]
+    serialize_roundtrip: bool,
+    #[serde(default)]
+    assignment: ConsumerAssignment,
+    #[serde(default)]
+    retain_history: bool,
 }
 
-impl Default for InMemoryBrokerConfig {
-    fn default() -> Self {
-        Self {
-            requeue_on_nack: false,
-        }
-    }
+/// The events held by the in-memory broker for a channel, as reported by
+/// [`InMemoryBroker::stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelStats {
+    /// The number of events published but not yet taken by a consumer, counted once per
+    /// consumer tag they are delivered to.
+    pub buffered_events: usize,
+    /// The number of events retained in the channel's history.
+    pub retained_events: usize,
+    /// The approximate size in bytes of the buffered and retained events, as the size of
+    /// their serialized data.
+    pub estimated_bytes: usize,
 }
 
+/// The events held by the in-memory broker across channels, as reported by
+/// [`InMemoryBroker::stats`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BrokerStats {
+    /// The number of events buffered across channels.
+    pub buffered_events: usize,
+    /// The number of events retained in the histories of every channel.
+    pub retained_events: usize,
+    /// The approximate size in bytes of the buffered and retained events.
+    pub estimated_bytes: usize,
+    /// The stats of every channel holding events.
+    pub channels: HashMap<String, ChannelStats>,
+}
+
+/// The interval at which [`InMemoryBroker::wait_for_consumers`] checks for consumers,
+/// and [`InMemoryBroker::drain`] for buffered events.
+const CONSUMER_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
 #[derive(Debug, Clone)]
 pub struct InMemoryBroker {
     config: InMemoryBrokerConfig,
@@ -38,8 +87,166 @@ pub struct InMemoryBroker {
 impl InMemoryBroker {
     pub fn new(config: InMemoryBrokerConfig) -> Self {
         Self {
+            inner: Arc::new(BrokerInner::new(config.requeue_on_nack, config.assignment, config.retain_history)),
             config,
-            inner: Arc::new(BrokerInner::new()),
+        }
+    }
+
+    /// Returns whether nacked messages are currently requeued by default.
+    pub fn requeue_on_nack(&self) -> bool {
+        self.inner.requeue_on_nack()
+    }
+
+    /// Change whether nacked messages are requeued by default.
+    /// 
+    /// The change applies to existing consumers as well as ones created afterwards,
+    /// except for consumers that override the default through their options.
+    /// 
+    /// # Arguments
+    /// * `value` - Whether nacked messages should be requeued.
+    pub async fn set_requeue_on_nack(&self, value: bool) {
+        self.inner.set_requeue_on_nack(value);
+    }
+
+    /// Returns the index, in registration order, of the consumer the next round-robin
+    /// event of a consumer group is assigned to.
+    /// 
+    /// # Arguments
+    /// * `channel` - The channel of the consumer group.
+    /// * `consumer_tag` - The consumer tag of the consumer group.
+    /// 
+    /// # Returns
+    /// The index of the consumer, or `None` if the group has no consumers.
+    pub async fn next_consumer_index(&self, channel: &str, consumer_tag: &str) -> Option<usize> {
+        self.inner
+            .next_consumer_index(channel, consumer_tag)
+            .await
+    }
+
+    /// Set the index, in registration order, of the consumer the next round-robin event
+    /// of a consumer group is assigned to, wrapping around the number of consumers.
+    /// 
+    /// The group is created if no consumer has registered with it yet, so the index can
+    /// be seeded before the consumers are created.
+    /// 
+    /// # Arguments
+    /// * `channel` - The channel of the consumer group.
+    /// * `consumer_tag` - The consumer tag of the consumer group.
+    /// * `index` - The index of the consumer to assign the next event to.
+    pub async fn seed_consumer_index(&self, channel: &str, consumer_tag: &str, index: usize) {
+        self.inner
+            .seed_consumer_index(channel, consumer_tag, index)
+            .await;
+    }
+
+    /// Returns the events published to a channel, in publishing order.
+    /// 
+    /// Events are only retained when the broker is built with
+    /// [`InMemoryBrokerBuilder::with_retain_history`], whether or not they were delivered.
+    /// Requeued events are not recorded again.
+    /// 
+    /// # Arguments
+    /// * `channel` - The channel to return the history of.
+    /// 
+    /// # Returns
+    /// The published events, empty if history is not retained.
+    pub async fn history(&self, channel: &str) -> Vec<Event> {
+        self.inner.history(channel)
+    }
+
+    /// Close a channel, ending the event streams of every consumer registered with it.
+    /// 
+    /// Events already delivered to a stream are still yielded before it ends, while events
+    /// published to the channel afterwards only reach consumers that subscribe again.
+    /// 
+    /// # Arguments
+    /// * `channel` - The channel to close.
+    pub async fn close_channel(&self, channel: &str) {
+        self.inner
+            .close_channel(channel)
+            .await;
+    }
+
+    /// Wait until at least the given number of consumers are registered with a channel,
+    /// so events published afterwards reach all of them.
+    /// 
+    /// Consumers count once registered, by [`InMemoryConsumer::subscribe`] or
+    /// [`Consumer::stream_events`](enroute_core::consumer::Consumer::stream_events), and
+    /// stop counting once their stream is dropped.
+    /// 
+    /// # Arguments
+    /// * `channel` - The channel to wait for consumers of.
+    /// * `count` - The number of consumers to wait for, across consumer tags.
+    /// * `timeout` - How long to wait before failing.
+    /// 
+    /// # Returns
+    /// A result indicating success, or an [`Error::Timeout`] if the consumers are not
+    /// registered in time.
+    pub async fn wait_for_consumers(&self, channel: &str, count: usize, timeout: Duration) -> Result<()> {
+        async {
+            while self.inner.consumer_count(channel).await < count {
+                Delay::new(CONSUMER_POLL_INTERVAL).await;
+            }
+        }
+        .timeout(timeout)
+        .await
+        .map_err(|_| Error::Timeout(format!("waiting for {} consumers of {}", count, channel)))
+    }
+
+    /// Wait until every event routed to consumers has been taken by their streams, as a
+    /// barrier after publishing a batch of events in tests.
+    /// 
+    /// Events count as taken once yielded by a consumer's stream, whether or not they are
+    /// acked yet, while events held back by a paused consumer keep the broker from
+    /// draining. Events nacked and requeued meanwhile must be taken again.
+    /// 
+    /// # Arguments
+    /// * `timeout` - How long to wait before failing.
+    /// 
+    /// # Returns
+    /// A result indicating success, or an [`Error::Timeout`] if events are still buffered
+    /// once the timeout elapses.
+    pub async fn drain(&self, timeout: Duration) -> Result<()> {
+        async {
+            while !self.inner.buffered().is_empty() {
+                Delay::new(CONSUMER_POLL_INTERVAL).await;
+            }
+        }
+        .timeout(timeout)
+        .await
+        .map_err(|_| Error::Timeout("waiting for buffered events to be consumed".to_string()))
+    }
+
+    /// Returns the number and approximate size of the events the broker holds, to detect
+    /// unbounded growth, e.g. of events published faster than they are consumed.
+    /// 
+    /// Events count as buffered from when they are routed to a consumer until the
+    /// consumer's stream yields them or is dropped, including while it is paused.
+    /// 
+    /// # Returns
+    /// The stats of the broker and of every channel holding events.
+    pub async fn stats(&self) -> BrokerStats {
+        let mut channels: HashMap<String, ChannelStats> = HashMap::new();
+
+        for (channel, buffered) in self.inner.buffered() {
+            let stats = channels.entry(channel).or_default();
+
+            stats.buffered_events += buffered.events;
+            stats.estimated_bytes += buffered.bytes;
+        }
+
+        for (channel, retained) in self.inner.retained() {
+            let stats = channels.entry(channel).or_default();
+
+            stats.retained_events += retained.events;
+            stats.estimated_bytes += retained.bytes;
+        }
+
+        BrokerStats {
+            buffered_events: channels.values().map(|stats| stats.buffered_events).sum(),
+            retained_events: channels.values().map(|stats| stats.retained_events).sum(),
+            estimated_bytes: channels.values().map(|stats| stats.estimated_bytes).sum(),
+            channels,
         }
     }
 
@@ -52,10 +259,13 @@ impl InMemoryBroker {
 impl Broker for InMemoryBroker {
     type Publisher = InMemoryPublisher;
     type Consumer = InMemoryConsumer;
+    type Admin = InMemoryAdmin;
 
     async fn publisher(&self, options: PublisherOptions) -> Result<Self::Publisher> {
         Ok(InMemoryPublisher {
             channel: options.channel.to_string(),
+            default_extensions: options.default_extensions,
+            strict_channel_check: options.strict_channel_check,
             inner: self.inner.clone(),
         })
     }
@@ -64,7 +274,22 @@ impl Broker for InMemoryBroker {
         Ok(InMemoryConsumer {
             channel: options.channel.to_string(),
             tag: options.consumer_tag.to_string(),
-            requeue: self.config.requeue_on_nack,
+            requeue: options.requeue_override
+                .or(options.delivery_guarantee.map(|g| g == DeliveryGuarantee::AtLeastOnce)),
+            serialize_roundtrip: self.config.serialize_roundtrip,
+            max_in_flight: options.max_in_flight,
+            replay_from_start: options.replay_from_start,
+            pause: PauseGate::new(),
+            inner: self.inner.clone(),
+        })
+    }
+
+    async fn channels(&self) -> Result<Vec<String>> {
+        Ok(self.inner.channels().await)
+    }
+
+    async fn admin(&self) -> Result<Self::Admin> {
+        Ok(InMemoryAdmin {
             inner: self.inner.clone(),
         })
     }
@@ -73,12 +298,18 @@ impl Broker for InMemoryBroker {
 
 pub struct InMemoryBrokerBuilder {
     requeue_on_nack: bool,
+    serialize_roundtrip: bool,
+    assignment: ConsumerAssignment,
+    retain_history: bool,
 }
 
 impl InMemoryBrokerBuilder {
     pub fn new() -> Self {
         Self {
             requeue_on_nack: false,
+            serialize_roundtrip: false,
+            assignment: ConsumerAssignment::default(),
+            retain_history: false,
         }
     }
 
@@ -86,6 +317,34 @@ impl InMemoryBrokerBuilder {
         self.requeue_on_nack = requeue;
         self
     }
+
+    /// Serialize every event to bytes and back before it is delivered, simulating the
+    /// round-trip through a real broker to surface serialization bugs.
+    pub fn with_serialize_roundtrip(mut self, roundtrip: bool) -> Self {
+        self.serialize_roundtrip = roundtrip;
+        self
+    }
+
+    /// Set how events are assigned to the consumers sharing a consumer tag.
+    pub fn with_assignment(mut self, assignment: ConsumerAssignment) -> Self {
+        self.assignment = assignment;
+        self
+    }
+
+    /// Retain every published event, delivered or not, so it can be queried with
+    /// [`InMemoryBroker::history`], and replayed to consumers created with
+    /// [`ConsumerOptions::replay_from_start`]. History grows without bound, so this is
+    /// meant for tests and debugging.
+    pub fn with_retain_history(mut self, retain: bool) -> Self {
+        self.retain_history = retain;
+        self
+    }
+}
+
+impl Default for InMemoryBrokerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[async_trait]
@@ -96,6 +355,9 @@ impl BrokerBuilder for InMemoryBrokerBuilder {
         Ok(InMemoryBroker::new(
             InMemoryBrokerConfig {
                 requeue_on_nack: self.requeue_on_nack,
+                serialize_roundtrip: self.serialize_roundtrip,
+                assignment: self.assignment,
+                retain_history: self.retain_history,
             }
         ))
     }
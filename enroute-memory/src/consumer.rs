@@ -1,40 +1,115 @@
 use std::{sync::Arc, pin::Pin};
 use async_trait::async_trait;
-use futures::{Stream, StreamExt};
+use futures::{Stream, StreamExt, channel::mpsc::UnboundedReceiver};
 
 use enroute_core::{
     consumer::Consumer,
+    in_flight::limit_in_flight,
+    pause::PauseGate,
     envelope::Envelope,
-    error::Result,
+    event::Event,
+    error::{Error, Result},
 };
 
-use crate::{inner::BrokerInner, acker::InMemoryAcker};
+use crate::{inner::{BrokerInner, QueuedEvent}, acker::InMemoryAcker};
 
 
 #[derive(Clone)]
 pub struct InMemoryConsumer {
     pub(crate) channel: String,
     pub(crate) tag: String,
-    pub(crate) requeue: bool,
+    pub(crate) requeue: Option<bool>,
+    pub(crate) serialize_roundtrip: bool,
+    pub(crate) max_in_flight: Option<usize>,
+    pub(crate) replay_from_start: bool,
+    pub(crate) pause: PauseGate,
     pub(crate) inner: Arc<BrokerInner>,
 }
 
+fn roundtrip(event: &Event) -> Result<Event> {
+    let bytes = serde_json::to_vec(event)
+        .map_err(|e| Error::Serialization(e.to_string()))?;
+
+    serde_json::from_slice(&bytes)
+        .map_err(|e| Error::Deserialization(e.to_string()))
+}
+
+impl InMemoryConsumer {
+    /// Register the consumer with its channel.
+    /// 
+    /// Events published once this returns are routed to the returned [`Subscription`],
+    /// even if its stream is not polled yet. Use this instead of
+    /// [`Consumer::stream_events`] when events may be published before streaming starts.
+    /// 
+    /// # Returns
+    /// A result containing the subscription or an error.
+    pub async fn subscribe(&self) -> Result<Subscription> {
+        Ok(Subscription {
+            consumer: self.clone(),
+            events: self.inner
+                .register_consumer(&self.channel, &self.tag, self.replay_from_start)
+                .await,
+        })
+    }
+}
+
 #[async_trait]
 impl Consumer for InMemoryConsumer {
     async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
-        let inner_weak = Arc::downgrade(&self.inner);
-        let channel_name = self.channel.clone();
-        let requeue = self.requeue.clone();
-
-        Ok(Box::pin(
-            self.inner
-                .register_consumer(&self.channel, &self.tag)
-                .await
-                .filter_map(move |event| {
+        Ok(self.subscribe().await?.into_stream())
+    }
+
+    /// Pause the streams of the consumer and its clones. Events published while paused
+    /// are queued and yielded once resumed.
+    async fn pause(&self) -> Result<()> {
+        self.pause.pause().await;
+        Ok(())
+    }
+
+    async fn resume(&self) -> Result<()> {
+        self.pause.resume().await;
+        Ok(())
+    }
+}
+
+
+/// A registration of an [`InMemoryConsumer`] with its channel, created by
+/// [`InMemoryConsumer::subscribe`].
+pub struct Subscription {
+    consumer: InMemoryConsumer,
+    events: UnboundedReceiver<QueuedEvent>,
+}
+
+impl Subscription {
+    /// Consume the subscription and stream the events routed to it, starting with
+    /// the ones published since it was created.
+    /// 
+    /// # Returns
+    /// A stream of message envelopes.
+    pub fn into_stream(self) -> Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>> {
+        let inner_weak = Arc::downgrade(&self.consumer.inner);
+        let channel_name = self.consumer.channel.clone();
+        let requeue = self.consumer.requeue;
+        let serialize_roundtrip = self.consumer.serialize_roundtrip;
+
+        let envelopes = Box::pin(
+            self.consumer.pause
+                .gate(self.events)
+                .filter_map(move |queued| {
                     let inner_weak = inner_weak.clone();
                     let channel_name = channel_name.clone();
+                    let event = queued.event;
 
                     async move {
+                        let event = if serialize_roundtrip {
+                            match roundtrip(&event) {
+                                Ok(event) => event,
+                                Err(e) => return Some(Err(e)),
+                            }
+                        } else {
+                            event
+                        };
+
                         Some(Ok(Envelope::new(
                             event.clone(),
                             Arc::new(InMemoryAcker::new(
@@ -46,6 +121,80 @@ impl Consumer for InMemoryConsumer {
                         )))
                     }
                 })
-        ))
+        );
+
+        match self.consumer.max_in_flight {
+            Some(max) => limit_in_flight(envelopes, max),
+            None => envelopes,
+        }
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use futures::StreamExt;
+    use futures_timeout::TimeoutExt;
+
+    use enroute_core::{
+        broker::{Broker, BrokerBuilder},
+        consumer::{Consumer, ConsumerOptions},
+        publisher::{Publisher, PublisherOptions},
+        event::EventBuilder,
+    };
+
+    use crate::broker::InMemoryBroker;
+
+    #[tokio::test]
+    async fn receives_events_published_right_after_subscribing() {
+        let broker = InMemoryBroker::builder().build().await.unwrap();
+        let publisher = broker
+            .publisher(PublisherOptions::builder().channel("orders").build())
+            .await
+            .unwrap();
+        let consumer = broker
+            .consumer(ConsumerOptions::builder().channel("orders").consumer_tag("test").build())
+            .await
+            .unwrap();
+        let event = EventBuilder::new()
+            .source("test")
+            .type_("order.placed")
+            .build_raw(b"{}".to_vec())
+            .unwrap();
+
+        let subscription = consumer.subscribe().await.unwrap();
+        publisher.publish_event(event.clone()).await.unwrap();
+
+        let envelope = subscription
+            .into_stream()
+            .next()
+            .timeout(Duration::from_secs(1))
+            .await
+            .expect("the event was not received")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(envelope.event().id(), event.id());
+    }
+
+    #[tokio::test]
+    async fn ends_open_streams_when_the_channel_is_closed() {
+        let broker = InMemoryBroker::builder().build().await.unwrap();
+        let consumer = broker
+            .consumer(ConsumerOptions::builder().channel("orders").consumer_tag("test").build())
+            .await
+            .unwrap();
+
+        let mut events = consumer.stream_events().await.unwrap();
+        broker.close_channel("orders").await;
+
+        let next = events
+            .next()
+            .timeout(Duration::from_secs(1))
+            .await
+            .expect("the stream did not end");
+
+        assert!(next.is_none());
+    }
+}
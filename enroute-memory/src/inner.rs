@@ -1,20 +1,118 @@
 use std::{
     collections::HashMap,
-    sync::Arc,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}},
     fmt::Debug,
 };
 use futures::{SinkExt, channel::mpsc::{UnboundedReceiver, UnboundedSender, unbounded}};
-use mea::rwlock::RwLock;
+use mea::{mutex::Mutex as AsyncMutex, rwlock::RwLock};
 
 use enroute_core::{
-    event::Event,
+    event::{Event, extension_value_to_string},
     error::{Error, Result},
 };
 
+use crate::{broker::ConsumerAssignment, publisher::SEQUENCE_EXTENSION};
+
+
+/// The extension naming the key events are assigned to consumers by, with
+/// [`ConsumerAssignment::KeyHash`].
+const PARTITION_KEY_EXTENSION: &str = "partitionkey";
+
+fn assignment_key(event: &Event) -> Option<String> {
+    event.0
+        .extension(PARTITION_KEY_EXTENSION)
+        .map(extension_value_to_string)
+        .or_else(|| event.subject().map(str::to_string))
+}
+
+/// Returns the approximate size of an event, as the size of its serialized data.
+pub(crate) fn event_size(event: &Event) -> usize {
+    event
+        .data_as_bytes()
+        .map(|bytes| bytes.len())
+        .unwrap_or(0)
+}
+
+
+/// The number and approximate size of the events buffered for the consumers of a
+/// channel.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Buffered {
+    pub(crate) events: usize,
+    pub(crate) bytes: usize,
+}
+
+/// Counts the events buffered for consumers, per channel.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct BufferCounter(Arc<Mutex<HashMap<String, Buffered>>>);
+
+impl BufferCounter {
+    fn buffers(&self) -> std::sync::MutexGuard<'_, HashMap<String, Buffered>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Count an event as buffered until the returned [`QueuedEvent`] is dropped.
+    fn enqueue(&self, channel: &str, event: Event, bytes: usize) -> QueuedEvent {
+        let mut buffers = self.buffers();
+        let buffered = buffers
+            .entry(channel.to_string())
+            .or_default();
+
+        buffered.events += 1;
+        buffered.bytes += bytes;
+
+        QueuedEvent {
+            event,
+            _guard: BufferGuard {
+                counter: self.clone(),
+                channel: channel.to_string(),
+                bytes,
+            },
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> HashMap<String, Buffered> {
+        self.buffers().clone()
+    }
+}
+
+#[derive(Debug)]
+struct BufferGuard {
+    counter: BufferCounter,
+    channel: String,
+    bytes: usize,
+}
+
+impl Drop for BufferGuard {
+    fn drop(&mut self) {
+        let mut buffers = self.counter.buffers();
+
+        if let Some(buffered) = buffers.get_mut(&self.channel) {
+            buffered.events -= 1;
+            buffered.bytes -= self.bytes;
+
+            if buffered.events == 0 {
+                buffers.remove(&self.channel);
+            }
+        }
+    }
+}
+
+/// An event buffered for a consumer, counted by its [`BufferCounter`] until the consumer
+/// takes it or its stream is dropped.
+#[derive(Debug)]
+pub(crate) struct QueuedEvent {
+    pub(crate) event: Event,
+    _guard: BufferGuard,
+}
+
 
 #[derive(Debug)]
 pub(crate) struct ConsumerGroup {
-    consumers: Vec<UnboundedSender<Event>>,
+    consumers: Vec<UnboundedSender<QueuedEvent>>,
     idx: usize,
 }
 
@@ -26,22 +124,42 @@ impl ConsumerGroup {
         }
     }
 
-    fn add_consumer(&mut self) -> UnboundedReceiver<Event> {
+    fn add_consumer(&mut self, backlog: Vec<QueuedEvent>) -> UnboundedReceiver<QueuedEvent> {
         let (tx, rx) = unbounded();
+
+        for event in backlog {
+            let _ = tx.unbounded_send(event);
+        }
+
         self.consumers.push(tx);
         rx
     }
 
-    async fn dispatch(&mut self, event: &Event) -> Result<()> {
+    async fn dispatch(&mut self, event: QueuedEvent, assignment: ConsumerAssignment) -> Result<()> {
         if self.consumers.is_empty() {
             return Ok(());
         }
 
-        let idx = self.idx % self.consumers.len();
-        self.idx = (self.idx + 1) % self.consumers.len();
+        let key = match assignment {
+            ConsumerAssignment::RoundRobin => None,
+            ConsumerAssignment::KeyHash => assignment_key(&event.event),
+        };
+        let idx = match key {
+            Some(key) => {
+                let mut hasher = DefaultHasher::new();
+                key.hash(&mut hasher);
+
+                (hasher.finish() % self.consumers.len() as u64) as usize
+            }
+            None => {
+                let idx = self.idx % self.consumers.len();
+                self.idx = (self.idx + 1) % self.consumers.len();
+                idx
+            }
+        };
 
         self.consumers[idx]
-            .send(event.clone())
+            .send(event)
             .await
             .map_err(|e| Error::Unknown(e.into()))?;
 
@@ -50,19 +168,100 @@ impl ConsumerGroup {
 }
 
 
+type ChannelGroups = HashMap<String, Arc<RwLock<ConsumerGroup>>>;
+
 #[derive(Debug)]
 pub(crate) struct BrokerInner {
-    groups: RwLock<HashMap<String, HashMap<String, Arc<RwLock<ConsumerGroup>>>>>,
+    groups: RwLock<HashMap<String, ChannelGroups>>,
+    requeue_on_nack: AtomicBool,
+    assignment: ConsumerAssignment,
+    sequences: Mutex<HashMap<String, Arc<AsyncMutex<u64>>>>,
+    retain_history: bool,
+    history: Mutex<HashMap<String, Vec<Event>>>,
+    buffered: BufferCounter,
 }
 
 impl BrokerInner {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(requeue_on_nack: bool, assignment: ConsumerAssignment, retain_history: bool) -> Self {
         Self {
             groups: RwLock::new(HashMap::new()),
+            requeue_on_nack: AtomicBool::new(requeue_on_nack),
+            assignment,
+            sequences: Mutex::new(HashMap::new()),
+            retain_history,
+            history: Mutex::new(HashMap::new()),
+            buffered: BufferCounter::default(),
+        }
+    }
+
+    pub fn requeue_on_nack(&self) -> bool {
+        self.requeue_on_nack.load(Ordering::SeqCst)
+    }
+
+    pub fn set_requeue_on_nack(&self, value: bool) {
+        self.requeue_on_nack.store(value, Ordering::SeqCst);
+    }
+
+    /// Returns the counter of the sequence numbers stamped on the events of a channel.
+    fn sequence(&self, channel: &str) -> Arc<AsyncMutex<u64>> {
+        self.sequences
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(channel.to_string())
+            .or_default()
+            .clone()
+    }
+
+    fn record(&self, channel: &str, event: &Event) {
+        if !self.retain_history {
+            return;
         }
+
+        self.history
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(channel.to_string())
+            .or_default()
+            .push(event.clone());
+    }
+
+    pub fn history(&self, channel: &str) -> Vec<Event> {
+        self.history
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(channel)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns the events retained in the history of every channel.
+    pub(crate) fn retained(&self) -> HashMap<String, Buffered> {
+        self.history
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(channel, events)| (channel.clone(), Buffered {
+                events: events.len(),
+                bytes: events.iter().map(event_size).sum(),
+            }))
+            .collect()
+    }
+
+    /// Returns the events buffered for consumers, per channel.
+    pub(crate) fn buffered(&self) -> HashMap<String, Buffered> {
+        self.buffered.snapshot()
     }
 
-    pub async fn register_consumer(&self, channel: &str, consumer_tag: &str) -> UnboundedReceiver<Event> {
+    pub async fn channels(&self) -> Vec<String> {
+        self.groups
+            .read()
+            .await
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    async fn group(&self, channel: &str, consumer_tag: &str) -> Arc<RwLock<ConsumerGroup>> {
         self.groups
             .write()
             .await
@@ -70,15 +269,136 @@ impl BrokerInner {
             .or_default()
             .entry(consumer_tag.to_string())
             .or_insert_with(|| Arc::new(RwLock::new(ConsumerGroup::new())))
+            .clone()
+    }
+
+    pub(crate) async fn register_consumer(&self, channel: &str, consumer_tag: &str, replay: bool) -> UnboundedReceiver<QueuedEvent> {
+        if !replay {
+            return self.group(channel, consumer_tag)
+                .await
+                .write()
+                .await
+                .add_consumer(Vec::new());
+        }
+
+        // Holding the channels lock keeps events from being recorded in between reading
+        // the history and registering, so none is missed or received twice.
+        let mut groups = self.groups.write().await;
+        let group = groups
+            .entry(channel.to_string())
+            .or_default()
+            .entry(consumer_tag.to_string())
+            .or_insert_with(|| Arc::new(RwLock::new(ConsumerGroup::new())))
+            .clone();
+
+        let backlog = self
+            .history(channel)
+            .into_iter()
+            .map(|event| {
+                let bytes = event_size(&event);
+
+                self.buffered.enqueue(channel, event, bytes)
+            })
+            .collect();
+
+        group
             .write()
             .await
-            .add_consumer()
+            .add_consumer(backlog)
+    }
+
+    pub async fn consumer_count(&self, channel: &str) -> usize {
+        let Some(consumer_tags) = self.groups
+            .read()
+            .await
+            .get(channel)
+            .map(|groups| groups.values().cloned().collect::<Vec<_>>())
+        else {
+            return 0;
+        };
+        let mut count = 0;
+
+        for group in consumer_tags {
+            count += group
+                .read()
+                .await
+                .consumers
+                .iter()
+                .filter(|consumer| !consumer.is_closed())
+                .count();
+        }
+
+        count
+    }
+
+    pub async fn next_consumer_index(&self, channel: &str, consumer_tag: &str) -> Option<usize> {
+        let group = self.groups
+            .read()
+            .await
+            .get(channel)?
+            .get(consumer_tag)?
+            .clone();
+        let group = group.read().await;
+
+        (!group.consumers.is_empty()).then(|| group.idx % group.consumers.len())
+    }
+
+    pub async fn seed_consumer_index(&self, channel: &str, consumer_tag: &str, index: usize) {
+        self.group(channel, consumer_tag)
+            .await
+            .write()
+            .await
+            .idx = index;
+    }
+
+    pub async fn create_channel(&self, channel: &str) {
+        self.groups
+            .write()
+            .await
+            .entry(channel.to_string())
+            .or_default();
+    }
+
+    pub async fn close_channel(&self, channel: &str) {
+        self.groups
+            .write()
+            .await
+            .remove(channel);
     }
 
     pub async fn publish(&self, channel: &str, event: &Event) -> Result<()> {
-        if let Some(consumer_tags) = self.groups.read().await.get(channel) {
+        self.dispatch(channel, event, false).await
+    }
+
+    /// Publish an event, stamping it with the channel's next sequence number and
+    /// recording it in the channel's history.
+    /// 
+    /// The channel's sequence stays locked until the event is dispatched to every
+    /// consumer group, so concurrent publishes are delivered in sequence order.
+    pub async fn publish_sequenced(&self, channel: &str, mut event: Event) -> Result<()> {
+        let sequence = self.sequence(channel);
+        let mut sequence = sequence.lock().await;
+
+        *sequence += 1;
+        event.0.set_extension(SEQUENCE_EXTENSION, sequence.to_string());
+
+        self.dispatch(channel, &event, true).await
+    }
+
+    async fn dispatch(&self, channel: &str, event: &Event, record: bool) -> Result<()> {
+        let groups = self.groups.read().await;
+
+        if record {
+            self.record(channel, event);
+        }
+
+        if let Some(consumer_tags) = groups.get(channel) {
+            let bytes = event_size(event);
+
             for group in consumer_tags.values() {
-                group.write().await.dispatch(event).await?;
+                let queued = self.buffered.enqueue(channel, event.clone(), bytes);
+
+                group.write().await.dispatch(queued, self.assignment).await?;
             }
         }
 
@@ -6,10 +6,12 @@ pub mod consumer;
 pub mod broker;
 pub mod inner;
 pub mod acker;
+pub mod admin;
 
 pub use crate::{
-    broker::{InMemoryBroker, InMemoryBrokerBuilder, InMemoryBrokerConfig},
-    consumer::InMemoryConsumer,
-    publisher::InMemoryPublisher,
+    broker::{InMemoryBroker, InMemoryBrokerBuilder, InMemoryBrokerConfig, ConsumerAssignment, BrokerStats, ChannelStats},
+    consumer::{InMemoryConsumer, Subscription},
+    publisher::{InMemoryPublisher, SEQUENCE_EXTENSION},
     acker::InMemoryAcker,
+    admin::InMemoryAdmin,
 };
\ No newline at end of file
@@ -1,24 +1,65 @@
-use std::sync::Arc;
+use std::{sync::Arc, collections::HashMap, future::Future};
 use async_trait::async_trait;
 
-use enroute_core::{event::Event, error::Result, publisher::Publisher};
+use enroute_core::{
+    event::{Event, ExtensionValue},
+    error::Result,
+    publisher::{Publisher, NativePublisher},
+};
 
 use crate::inner::BrokerInner;
 
 
+/// The extension carrying the per-channel sequence number stamped on every event
+/// published through the in-memory broker, starting at 1, as a decimal string like the
+/// CloudEvents sequence extension. It replaces any sequence the published event carries.
+/// Requeued events keep the sequence number they were first published with.
+pub const SEQUENCE_EXTENSION: &str = "sequence";
+
 #[derive(Clone)]
 pub struct InMemoryPublisher {
     pub(crate) channel: String,
+    pub(crate) default_extensions: HashMap<String, ExtensionValue>,
+    pub(crate) strict_channel_check: bool,
     pub(crate) inner: Arc<BrokerInner>,
 }
 
+impl InMemoryPublisher {
+    pub async fn publish(&self, event: Event) -> Result<()> {
+        self.publish_to(&self.channel, event).await
+    }
+
+    /// Publish an event to the given channel instead of the publisher's own channel.
+    pub async fn publish_to(&self, channel: &str, mut event: Event) -> Result<()> {
+        event.apply_default_extensions(&self.default_extensions);
+
+        self.inner
+            .publish_sequenced(channel, event)
+            .await
+    }
+}
+
 #[async_trait]
 impl Publisher for InMemoryPublisher {
     async fn publish_event(&self, event: Event) -> Result<()> {
-        self.inner
-            .publish(&self.channel, &event)
-            .await?;
+        self.publish(event).await
+    }
+
+    async fn publish_event_to(&self, channel: &str, event: Event) -> Result<()> {
+        self.publish_to(channel, event).await
+    }
 
-        Ok(())
+    fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    fn strict_channel_check(&self) -> bool {
+        self.strict_channel_check
     }
 }
+
+impl NativePublisher for InMemoryPublisher {
+    fn publish(&self, event: Event) -> impl Future<Output = Result<()>> + Send {
+        InMemoryPublisher::publish(self, event)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,56 @@
+use std::{
+    sync::{Arc, atomic::{AtomicBool, Ordering}},
+    fmt::{self, Debug},
+};
+use async_trait::async_trait;
+
+use enroute_core::envelope::Acker;
+
+use crate::client::{MqttClient, MqttPublish};
+
+
+/// An acker completing the QoS 1 or 2 handshake of an MQTT message once it is acked.
+///
+/// MQTT has no negative acknowledgement: nacking leaves the message unacknowledged, so
+/// the broker delivers it again when the consumer's session reconnects.
+#[derive(Clone)]
+pub struct MqttAcker {
+    client: MqttClient,
+    publish: MqttPublish,
+    done: Arc<AtomicBool>,
+}
+
+impl MqttAcker {
+    pub(crate) fn new(client: MqttClient, publish: MqttPublish) -> Self {
+        Self {
+            client,
+            publish,
+            done: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl Debug for MqttAcker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MqttAcker")
+            .field("publish", &self.publish)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl Acker for MqttAcker {
+    async fn ack(&self) {
+        if self.done.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let _ = self.client
+            .ack(&self.publish)
+            .await;
+    }
+
+    async fn nack(&self) {
+        self.done.store(true, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use uuid::Uuid;
+
+use enroute_core::{
+    publisher::PublisherOptions,
+    consumer::ConsumerOptions,
+    broker::{Broker, BrokerBuilder},
+    admin::UnsupportedAdmin,
+    error::{Error, Result},
+};
+
+use enroute_mqtt::{
+    client::{ConnectOptions, MqttVersion, validate_qos},
+    consumer::MqttConsumer,
+    publisher::MqttPublisher,
+};
+
+
+fn default_qos() -> u8 {
+    1
+}
+
+fn qos(extra: &HashMap<String, String>, default: u8) -> Result<u8> {
+    match extra.get("qos") {
+        Some(value) => value
+            .parse::<u8>()
+            .map_err(|_| Error::Builder(format!("invalid qos: {}", value)))
+            .and_then(validate_qos),
+        None => Ok(default),
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MqttBrokerConfig {
+    /// The URL of the MQTT broker, e.g. `mqtt://localhost:1883`.
+    pub url: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub version: MqttVersion,
+    /// The QoS level used by publishers and consumers that don't set their own.
+    #[serde(default = "default_qos")]
+    pub default_qos: u8,
+}
+
+impl MqttBrokerConfig {
+    fn connect_options(&self) -> Result<ConnectOptions> {
+        let credentials = match (&self.username, &self.password) {
+            (Some(username), password) => Some((username.clone(), password.clone().unwrap_or_default())),
+            (None, _) => None,
+        };
+
+        ConnectOptions::new(&self.url, credentials, self.version)
+    }
+}
+
+
+/// A broker publishing and consuming events through MQTT topics.
+///
+/// Every publisher and consumer has its own connection. Publishers and consumers accept
+/// the following extra settings:
+/// * `qos` - The QoS level (0, 1 or 2) to publish or subscribe with.
+#[derive(Debug, Clone)]
+pub struct MqttBroker {
+    config: MqttBrokerConfig,
+    options: ConnectOptions,
+}
+
+impl MqttBroker {
+    pub fn new(config: MqttBrokerConfig) -> Result<Self> {
+        validate_qos(config.default_qos)?;
+
+        Ok(Self {
+            options: config.connect_options()?,
+            config,
+        })
+    }
+
+    pub fn builder() -> MqttBrokerBuilder {
+        MqttBrokerBuilder::new()
+    }
+}
+
+#[async_trait]
+impl Broker for MqttBroker {
+    type Publisher = MqttPublisher;
+    type Consumer = MqttConsumer;
+    type Admin = UnsupportedAdmin;
+
+    async fn publisher(&self, options: PublisherOptions) -> Result<Self::Publisher> {
+        let qos = qos(&options.extra, self.config.default_qos)?;
+        let (client, event_loop) = self.options.connect(&format!("enroute-{}", Uuid::new_v4()), false);
+
+        event_loop.spawn();
+
+        Ok(
+            MqttPublisher::new(client, options.channel, qos)
+                .with_default_extensions(options.default_extensions)
+                .with_strict_channel_check(options.strict_channel_check)
+        )
+    }
+
+    async fn consumer(&self, options: ConsumerOptions) -> Result<Self::Consumer> {
+        let qos = qos(&options.extra, self.config.default_qos)?;
+        let (client, event_loop) = self.options.connect(&options.consumer_tag, true);
+
+        Ok(
+            MqttConsumer::new(client, event_loop, options.channel, qos)
+                .with_delivery_guarantee(options.delivery_guarantee)
+                .with_max_in_flight(options.max_in_flight)
+        )
+    }
+}
+
+
+pub struct MqttBrokerBuilder {
+    url: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    version: MqttVersion,
+    default_qos: u8,
+}
+
+impl MqttBrokerBuilder {
+    pub fn new() -> Self {
+        Self {
+            url: None,
+            username: None,
+            password: None,
+            version: MqttVersion::default(),
+            default_qos: default_qos(),
+        }
+    }
+
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    pub fn with_credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Set the MQTT protocol version spoken with the broker.
+    pub fn with_version(mut self, version: MqttVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Set the QoS level used by publishers and consumers that don't set their own.
+    pub fn with_default_qos(mut self, qos: u8) -> Self {
+        self.default_qos = qos;
+        self
+    }
+}
+
+impl Default for MqttBrokerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl BrokerBuilder for MqttBrokerBuilder {
+    type Broker = MqttBroker;
+
+    async fn build(&self) -> Result<Self::Broker> {
+        MqttBroker::new(MqttBrokerConfig {
+            url: self.url
+                .clone()
+                .ok_or_else(|| Error::Builder("missing url".to_string()))?,
+            username: self.username.clone(),
+            password: self.password.clone(),
+            version: self.version,
+            default_qos: self.default_qos,
+        })
+    }
+}
@@ -0,0 +1,372 @@
+use std::{collections::HashMap, time::Duration};
+use serde::{Serialize, Deserialize};
+use chrono::{DateTime, Utc};
+use url::Url;
+use futures::channel::mpsc;
+use rumqttc::{
+    v5::{
+        self,
+        mqttbytes::v5::{Packet as PacketV5, Publish as PublishV5, PublishProperties},
+    },
+    Event as EventV311,
+    Packet as PacketV311,
+    Publish as PublishV311,
+};
+
+use enroute_core::{
+    event::{Event, extension_value_to_string},
+    error::{Error, Result},
+};
+
+
+/// The capacity of the request channel between a client and its event loop.
+const REQUEST_CAPACITY: usize = 64;
+/// The delay before polling an event loop again after a connection error.
+pub(crate) const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// The attributes carried as `ce-*` user properties on MQTT v5.
+const ATTRIBUTE_PROPERTIES: [&str; 7] = [
+    "ce-specversion",
+    "ce-id",
+    "ce-type",
+    "ce-source",
+    "ce-time",
+    "ce-subject",
+    "ce-dataschema",
+];
+
+
+/// The MQTT protocol version spoken with the broker.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MqttVersion {
+    /// MQTT v3.1.1, which has no user properties. Events are sent as structured
+    /// CloudEvents JSON payloads.
+    #[serde(rename = "3.1.1")]
+    V311,
+    /// MQTT v5. Events are sent in the binary content mode, with their attributes and
+    /// extensions as `ce-*` user properties.
+    #[default]
+    #[serde(rename = "5")]
+    V5,
+}
+
+
+/// Validate a QoS level, which must be 0, 1 or 2.
+pub(crate) fn validate_qos(qos: u8) -> Result<u8> {
+    match qos {
+        0..=2 => Ok(qos),
+        _ => Err(Error::Builder(format!("invalid QoS: {}", qos))),
+    }
+}
+
+fn qos_v311(qos: u8) -> rumqttc::QoS {
+    rumqttc::qos(qos).unwrap_or(rumqttc::QoS::AtLeastOnce)
+}
+
+fn qos_v5(qos: u8) -> v5::mqttbytes::QoS {
+    v5::mqttbytes::qos(qos).unwrap_or(v5::mqttbytes::QoS::AtLeastOnce)
+}
+
+
+/// The connection settings shared by the clients of a broker.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectOptions {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) credentials: Option<(String, String)>,
+    pub(crate) version: MqttVersion,
+}
+
+impl ConnectOptions {
+    pub(crate) fn new(url: &str, credentials: Option<(String, String)>, version: MqttVersion) -> Result<Self> {
+        let url = Url::parse(url)
+            .map_err(|e| Error::Builder(format!("invalid url: {}", e)))?;
+
+        if url.scheme() != "mqtt" && url.scheme() != "tcp" {
+            return Err(Error::Builder(format!("unsupported url scheme: {}", url.scheme())));
+        }
+
+        Ok(Self {
+            host: url
+                .host_str()
+                .ok_or_else(|| Error::Builder("url has no host".to_string()))?
+                .to_string(),
+            port: url.port().unwrap_or(1883),
+            credentials,
+            version,
+        })
+    }
+
+    /// Create a client and its event loop, which connects once polled.
+    ///
+    /// # Arguments
+    /// * `client_id` - The client identifier.
+    /// * `persistent` - Whether the broker keeps the session, including subscriptions
+    ///   and unacknowledged messages, across reconnections.
+    pub(crate) fn connect(&self, client_id: &str, persistent: bool) -> (MqttClient, MqttEventLoop) {
+        match self.version {
+            MqttVersion::V311 => {
+                let mut options = rumqttc::MqttOptions::new(client_id, &self.host, self.port);
+
+                options
+                    .set_clean_session(!persistent)
+                    .set_manual_acks(true);
+
+                if let Some((username, password)) = &self.credentials {
+                    options.set_credentials(username, password);
+                }
+
+                let (client, event_loop) = rumqttc::AsyncClient::new(options, REQUEST_CAPACITY);
+
+                (MqttClient::V311(client), MqttEventLoop::V311(event_loop))
+            }
+            MqttVersion::V5 => {
+                let mut options = v5::MqttOptions::new(client_id, &self.host, self.port);
+
+                options
+                    .set_clean_start(!persistent)
+                    .set_manual_acks(true);
+
+                if persistent {
+                    options.set_session_expiry_interval(Some(u32::MAX));
+                }
+
+                if let Some((username, password)) = &self.credentials {
+                    options.set_credentials(username, password);
+                }
+
+                let (client, event_loop) = v5::AsyncClient::new(options, REQUEST_CAPACITY);
+
+                (MqttClient::V5(client), MqttEventLoop::V5(event_loop))
+            }
+        }
+    }
+}
+
+
+/// A client of either protocol version.
+#[derive(Clone)]
+pub(crate) enum MqttClient {
+    V311(rumqttc::AsyncClient),
+    V5(v5::AsyncClient),
+}
+
+impl MqttClient {
+    pub(crate) async fn publish(&self, topic: &str, qos: u8, event: &Event) -> Result<()> {
+        match self {
+            MqttClient::V311(client) => {
+                let payload = event.to_json_bytes()?;
+
+                client
+                    .publish(topic, qos_v311(qos), false, payload)
+                    .await
+                    .map_err(|e| Error::Publisher(e.to_string()))
+            }
+            MqttClient::V5(client) => {
+                let payload = event.data_as_bytes()?;
+                let mut user_properties = [
+                    ("ce-specversion", Some(event.specversion())),
+                    ("ce-id", Some(event.id().to_string())),
+                    ("ce-type", Some(event.type_().to_string())),
+                    ("ce-source", Some(event.source().to_string())),
+                    ("ce-time", event.time().map(|t| t.to_rfc3339())),
+                    ("ce-subject", event.subject().map(str::to_string)),
+                    ("ce-dataschema", event.dataschema().map(|url| url.as_str().to_string())),
+                ]
+                .into_iter()
+                .filter_map(|(key, value)| Some((key.to_string(), value?)))
+                .collect::<Vec<_>>();
+
+                user_properties.extend(
+                    event
+                        .extensions()
+                        .into_iter()
+                        .map(|(k, v)| (format!("ce-{}", k), extension_value_to_string(&v)))
+                );
+
+                let properties = PublishProperties {
+                    user_properties,
+                    content_type: event.datacontenttype().map(str::to_string),
+                    ..Default::default()
+                };
+
+                client
+                    .publish_with_properties(topic, qos_v5(qos), false, payload, properties)
+                    .await
+                    .map_err(|e| Error::Publisher(e.to_string()))
+            }
+        }
+    }
+
+    pub(crate) async fn subscribe(&self, topic: &str, qos: u8) -> Result<()> {
+        match self {
+            MqttClient::V311(client) => client
+                .subscribe(topic, qos_v311(qos))
+                .await
+                .map_err(|e| Error::Consumer(e.to_string())),
+            MqttClient::V5(client) => client
+                .subscribe(topic, qos_v5(qos))
+                .await
+                .map_err(|e| Error::Consumer(e.to_string())),
+        }
+    }
+
+    pub(crate) async fn ack(&self, publish: &MqttPublish) -> Result<()> {
+        match (self, publish) {
+            (MqttClient::V311(client), MqttPublish::V311(publish)) => client
+                .ack(publish)
+                .await
+                .map_err(|e| Error::Consumer(e.to_string())),
+            (MqttClient::V5(client), MqttPublish::V5(publish)) => client
+                .ack(publish)
+                .await
+                .map_err(|e| Error::Consumer(e.to_string())),
+            _ => Err(Error::Consumer("protocol version mismatch".to_string())),
+        }
+    }
+}
+
+
+/// An incoming publish of either protocol version.
+#[derive(Debug, Clone)]
+pub(crate) enum MqttPublish {
+    V311(PublishV311),
+    V5(PublishV5),
+}
+
+impl MqttPublish {
+    /// Convert the publish into an event, reading it in the binary content mode when it
+    /// carries `ce-*` user properties, and as structured CloudEvents JSON otherwise.
+    pub(crate) fn to_event(&self) -> Result<Event> {
+        let (payload, properties) = match self {
+            MqttPublish::V311(publish) => (&publish.payload, None),
+            MqttPublish::V5(publish) => (&publish.payload, publish.properties.as_ref()),
+        };
+        let user_properties = properties
+            .map(|p| p.user_properties
+                .iter()
+                .cloned()
+                .collect::<HashMap<_, _>>()
+            )
+            .unwrap_or_default();
+
+        if !user_properties.contains_key("ce-id") {
+            return serde_json::from_slice(payload)
+                .map_err(|e| Error::Deserialization(e.to_string()));
+        }
+
+        let get = |key: &str| user_properties
+            .get(key)
+            .cloned()
+            .ok_or_else(|| Error::Deserialization(format!("Missing {} property", key)));
+        let event_type = get("ce-type")?;
+        let content_type = properties
+            .and_then(|p| p.content_type.as_deref())
+            .unwrap_or("application/json");
+
+        Event::builder()
+            .id(get("ce-id")?)
+            .type_(event_type.as_str())
+            .source(get("ce-source")?)
+            .maybe_subject(user_properties.get("ce-subject").cloned())
+            .maybe_time(
+                user_properties
+                    .get("ce-time")
+                    .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.with_timezone(&Utc))
+            )
+            .maybe_schema_url(user_properties.get("ce-dataschema").map(String::as_str))
+            .extensions(
+                user_properties
+                    .iter()
+                    .filter(|(k, _)| !ATTRIBUTE_PROPERTIES.contains(&k.as_str()))
+                    .filter_map(|(k, v)| Some((k.strip_prefix("ce-")?, v.clone())))
+                    .collect::<HashMap<_, _>>()
+            )
+            .build_raw_with_content_type(&event_type, payload.to_vec(), content_type)
+    }
+}
+
+
+/// An event loop of either protocol version.
+pub(crate) enum MqttEventLoop {
+    V311(rumqttc::EventLoop),
+    V5(v5::EventLoop),
+}
+
+impl MqttEventLoop {
+    /// Poll the event loop until the next incoming publish.
+    ///
+    /// # Returns
+    /// A result containing the publish, `None` once every client of the event loop has
+    /// been dropped, or a connection error. The event loop reconnects when polled again
+    /// after an error.
+    pub(crate) async fn next_publish(&mut self) -> Result<Option<MqttPublish>> {
+        loop {
+            match self {
+                MqttEventLoop::V311(event_loop) => match event_loop.poll().await {
+                    Ok(EventV311::Incoming(PacketV311::Publish(publish))) => {
+                        return Ok(Some(MqttPublish::V311(publish)));
+                    }
+                    Ok(_) => {}
+                    Err(rumqttc::ConnectionError::RequestsDone) => return Ok(None),
+                    Err(e) => return Err(Error::Consumer(e.to_string())),
+                },
+                MqttEventLoop::V5(event_loop) => match event_loop.poll().await {
+                    Ok(v5::Event::Incoming(PacketV5::Publish(publish))) => {
+                        return Ok(Some(MqttPublish::V5(publish)));
+                    }
+                    Ok(_) => {}
+                    Err(v5::ConnectionError::RequestsDone) => return Ok(None),
+                    Err(e) => return Err(Error::Consumer(e.to_string())),
+                },
+            }
+        }
+    }
+
+    /// Drive the event loop in the background until every client of it has been
+    /// dropped, retrying after connection errors.
+    pub(crate) fn spawn(mut self) {
+        tokio::spawn(async move {
+            loop {
+                match self.next_publish().await {
+                    Ok(Some(_)) => {}
+                    Ok(None) => break,
+                    Err(_) => tokio::time::sleep(RECONNECT_DELAY).await,
+                }
+            }
+        });
+    }
+
+    /// Drive the event loop in the background, forwarding incoming publishes and
+    /// connection errors over a channel, so keepalives and acks keep flowing however
+    /// slowly the publishes are read. The event loop stops once every client of it, or
+    /// the receiver, has been dropped.
+    ///
+    /// # Returns
+    /// The receiver of the publishes.
+    pub(crate) fn spawn_publishes(mut self) -> mpsc::UnboundedReceiver<Result<MqttPublish>> {
+        let (sender, receiver) = mpsc::unbounded();
+
+        tokio::spawn(async move {
+            loop {
+                let publish = match self.next_publish().await {
+                    Ok(Some(publish)) => Ok(publish),
+                    Ok(None) => break,
+                    Err(e) => Err(e),
+                };
+                let failed = publish.is_err();
+
+                if sender.unbounded_send(publish).is_err() {
+                    break;
+                }
+
+                if failed {
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                }
+            }
+        });
+
+        receiver
+    }
+}
@@ -0,0 +1,137 @@
+use std::{sync::{Arc, Mutex}, pin::Pin};
+use async_trait::async_trait;
+use async_stream::stream;
+use futures::{Stream, StreamExt};
+
+use enroute_core::{
+    consumer::{Consumer, DeliveryGuarantee},
+    in_flight::limit_in_flight,
+    envelope::{Acker, Envelope, NoOpAcker},
+    error::{Error, Result},
+};
+
+use crate::{
+    acker::MqttAcker,
+    client::{MqttClient, MqttEventLoop},
+};
+
+
+/// An MQTT consumer subscribed to a topic.
+///
+/// The consumer connects with its consumer tag as client identifier and a persistent
+/// session, so QoS 1 and 2 messages that are not acked are delivered again once it
+/// reconnects. Consumer tags must therefore be unique per consumer, as the broker
+/// disconnects a client when another one connects with the same identifier.
+///
+/// Messages that can't be read as events are acked and yielded as errors, rather than
+/// holding up the session's in-flight window.
+///
+/// The connection is driven by a background task once streaming starts, so keepalives
+/// and acks go out while handlers are slow or the stream is held back by its in-flight
+/// limit. Messages received meanwhile are buffered until the stream reads them.
+///
+/// A consumer supports a single event stream: once [`Consumer::stream_events`] has been
+/// called, further calls fail.
+pub struct MqttConsumer {
+    client: MqttClient,
+    event_loop: Mutex<Option<MqttEventLoop>>,
+    topic: String,
+    qos: u8,
+    delivery_guarantee: Option<DeliveryGuarantee>,
+    max_in_flight: Option<usize>,
+}
+
+impl MqttConsumer {
+    pub(crate) fn new(client: MqttClient, event_loop: MqttEventLoop, topic: String, qos: u8) -> Self {
+        Self {
+            client,
+            event_loop: Mutex::new(Some(event_loop)),
+            topic,
+            qos,
+            delivery_guarantee: None,
+            max_in_flight: None,
+        }
+    }
+
+    /// Set the delivery guarantee of the consumer.
+    /// 
+    /// With [`DeliveryGuarantee::AtMostOnce`] each message is acked before its envelope
+    /// is yielded. Otherwise it is only acked when the envelope is acked.
+    pub fn with_delivery_guarantee(mut self, guarantee: Option<DeliveryGuarantee>) -> Self {
+        self.delivery_guarantee = guarantee;
+        self
+    }
+
+    /// Set the maximum number of envelopes yielded but not yet acked or nacked.
+    pub fn with_max_in_flight(mut self, max: Option<usize>) -> Self {
+        self.max_in_flight = max;
+        self
+    }
+
+    /// Returns the topic the consumer subscribes to.
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Returns the QoS level of the consumer's subscription.
+    pub fn qos(&self) -> u8 {
+        self.qos
+    }
+}
+
+#[async_trait]
+impl Consumer for MqttConsumer {
+    async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
+        let event_loop = self.event_loop
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+            .ok_or_else(|| Error::Consumer("consumer is already streaming events".to_string()))?;
+
+        self.client
+            .subscribe(&self.topic, self.qos)
+            .await?;
+
+        let client = self.client.clone();
+        let delivery_guarantee = self.delivery_guarantee;
+        let mut publishes = event_loop.spawn_publishes();
+        let stream = stream! {
+            while let Some(publish) = publishes.next().await {
+                let publish = match publish {
+                    Ok(publish) => publish,
+                    Err(e) => {
+                        yield Err(e);
+                        continue;
+                    }
+                };
+
+                let event = match publish.to_event() {
+                    Ok(event) => event,
+                    Err(e) => {
+                        let _ = client.ack(&publish).await;
+                        yield Err(e);
+                        continue;
+                    }
+                };
+                let acker: Arc<dyn Acker> = match delivery_guarantee {
+                    Some(DeliveryGuarantee::AtMostOnce) => {
+                        if let Err(e) = client.ack(&publish).await {
+                            yield Err(e);
+                            continue;
+                        }
+
+                        Arc::new(NoOpAcker)
+                    }
+                    _ => Arc::new(MqttAcker::new(client.clone(), publish)),
+                };
+
+                yield Ok(Envelope::new(event, acker));
+            }
+        };
+
+        Ok(match self.max_in_flight {
+            Some(max) => limit_in_flight(Box::pin(stream), max),
+            None => Box::pin(stream),
+        })
+    }
+}
@@ -0,0 +1,16 @@
+#[allow(unused_extern_crates)]
+extern crate self as enroute_mqtt;
+
+pub mod client;
+pub mod publisher;
+pub mod consumer;
+pub mod broker;
+pub mod acker;
+
+pub use crate::{
+    broker::{MqttBroker, MqttBrokerBuilder, MqttBrokerConfig},
+    client::MqttVersion,
+    consumer::MqttConsumer,
+    publisher::MqttPublisher,
+    acker::MqttAcker,
+};
@@ -0,0 +1,95 @@
+use std::{collections::HashMap, future::Future};
+use async_trait::async_trait;
+
+use enroute_core::{
+    event::{Event, ExtensionValue},
+    error::Result,
+    publisher::{Publisher, NativePublisher},
+};
+
+use crate::client::MqttClient;
+
+
+/// An MQTT publisher sending events to a topic.
+///
+/// On MQTT v5 events are sent in the binary content mode, with the event data as the
+/// payload and its attributes and extensions as `ce-*` user properties. On MQTT v3.1.1
+/// they are sent as structured CloudEvents JSON payloads.
+/// 
+/// Publishing returns once the message is queued on the connection, which is driven in
+/// the background and reconnects on errors.
+#[derive(Clone)]
+pub struct MqttPublisher {
+    client: MqttClient,
+    topic: String,
+    qos: u8,
+    default_extensions: HashMap<String, ExtensionValue>,
+    strict_channel_check: bool,
+}
+
+impl MqttPublisher {
+    pub(crate) fn new(client: MqttClient, topic: String, qos: u8) -> Self {
+        Self {
+            client,
+            topic,
+            qos,
+            default_extensions: HashMap::new(),
+            strict_channel_check: false,
+        }
+    }
+
+    /// Set whether [`Publisher::publish_typed`] rejects event data of another channel.
+    pub fn with_strict_channel_check(mut self, strict: bool) -> Self {
+        self.strict_channel_check = strict;
+        self
+    }
+
+    /// Set extensions applied to every published event that doesn't already carry them.
+    pub fn with_default_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.default_extensions = extensions;
+        self
+    }
+
+    /// Returns the QoS level events are published with.
+    pub fn qos(&self) -> u8 {
+        self.qos
+    }
+
+    pub async fn publish(&self, event: Event) -> Result<()> {
+        self.publish_to(&self.topic, event).await
+    }
+
+    /// Publish an event to the given topic instead of the publisher's own topic.
+    pub async fn publish_to(&self, topic: &str, mut event: Event) -> Result<()> {
+        event.apply_default_extensions(&self.default_extensions);
+
+        self.client
+            .publish(topic, self.qos, &event)
+            .await
+    }
+}
+
+#[async_trait]
+impl Publisher for MqttPublisher {
+    async fn publish_event(&self, event: Event) -> Result<()> {
+        self.publish(event).await
+    }
+
+    async fn publish_event_to(&self, channel: &str, event: Event) -> Result<()> {
+        self.publish_to(channel, event).await
+    }
+
+    fn channel(&self) -> &str {
+        &self.topic
+    }
+
+    fn strict_channel_check(&self) -> bool {
+        self.strict_channel_check
+    }
+}
+
+impl NativePublisher for MqttPublisher {
+    fn publish(&self, event: Event) -> impl Future<Output = Result<()>> + Send {
+        MqttPublisher::publish(self, event)
+    }
+}
@@ -0,0 +1,56 @@
+use std::{sync::{Arc, atomic::{AtomicBool, Ordering}}, time::Duration};
+use async_trait::async_trait;
+use gcloud_pubsub::subscriber::ReceivedMessage;
+
+use enroute_core::{envelope::Acker, error::{Error, Result}};
+
+
+/// An acker that acks or nacks a received Pub/Sub message.
+///
+/// Nacking sets the message's ack deadline to zero, so it is redelivered right away.
+/// Extending the deadline sets the ack deadline to the given duration from now.
+#[derive(Debug, Clone)]
+pub struct PubSubAcker {
+    message: ReceivedMessage,
+    done: Arc<AtomicBool>,
+}
+
+impl PubSubAcker {
+    pub(crate) fn new(message: ReceivedMessage) -> Self {
+        Self {
+            message,
+            done: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Returns the ack id of the message.
+    pub fn ack_id(&self) -> &str {
+        self.message.ack_id()
+    }
+}
+
+#[async_trait]
+impl Acker for PubSubAcker {
+    async fn ack(&self) {
+        if self.done.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let _ = self.message.ack().await;
+    }
+
+    async fn nack(&self) {
+        if self.done.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let _ = self.message.nack().await;
+    }
+
+    async fn extend_deadline(&self, by: Duration) -> Result<()> {
+        self.message
+            .modify_ack_deadline(i32::try_from(by.as_secs()).unwrap_or(i32::MAX))
+            .await
+            .map_err(|e| Error::Consumer(e.to_string()))
+    }
+}
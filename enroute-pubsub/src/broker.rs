@@ -0,0 +1,177 @@
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use gcloud_pubsub::client::{Client, ClientConfig};
+
+use enroute_core::{
+    publisher::PublisherOptions,
+    consumer::ConsumerOptions,
+    broker::{Broker, BrokerBuilder},
+    admin::UnsupportedAdmin,
+    error::{Error, Result},
+};
+
+use enroute_pubsub::{
+    consumer::PubSubConsumer,
+    publisher::PubSubPublisher,
+};
+
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PubSubBrokerConfig {
+    /// The project of the topics and subscriptions. When not set, it is taken from the
+    /// credentials, or from the emulator when `PUBSUB_EMULATOR_HOST` is set.
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// The topic used for every channel. When not set, each channel names its topic.
+    #[serde(default)]
+    pub topic: Option<String>,
+    /// The subscription used for every consumer. When not set, each consumer's tag
+    /// names its subscription.
+    #[serde(default)]
+    pub subscription: Option<String>,
+}
+
+impl PubSubBrokerConfig {
+    /// Create a Pub/Sub client from the configuration, loading credentials from the
+    /// environment.
+    pub async fn into_client(&self) -> Result<Client> {
+        let mut config = ClientConfig::default()
+            .with_auth()
+            .await
+            .map_err(|e| Error::Builder(e.to_string()))?;
+
+        if let Some(project_id) = &self.project_id {
+            config.project_id = Some(project_id.clone());
+        }
+
+        Client::new(config)
+            .await
+            .map_err(|e| Error::Builder(e.to_string()))
+    }
+}
+
+
+/// A broker publishing and consuming events through Google Cloud Pub/Sub.
+///
+/// Channels name topics and consumer tags name subscriptions, which must already exist.
+#[derive(Clone)]
+pub struct PubSubBroker {
+    config: PubSubBrokerConfig,
+    client: Client,
+}
+
+impl PubSubBroker {
+    pub fn new(config: PubSubBrokerConfig, client: Client) -> Self {
+        Self { config, client }
+    }
+
+    pub fn builder() -> PubSubBrokerBuilder {
+        PubSubBrokerBuilder::new()
+    }
+
+    /// Returns a reference to the underlying client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl Broker for PubSubBroker {
+    type Publisher = PubSubPublisher;
+    type Consumer = PubSubConsumer;
+    type Admin = UnsupportedAdmin;
+
+    async fn publisher(&self, options: PublisherOptions) -> Result<Self::Publisher> {
+        let topic = self.config.topic
+            .clone()
+            .unwrap_or(options.channel);
+
+        Ok(
+            PubSubPublisher::new(
+                self.client
+                    .topic(&topic)
+                    .new_publisher(None),
+                topic,
+            )
+            .with_default_extensions(options.default_extensions)
+            .with_strict_channel_check(options.strict_channel_check)
+            .with_client(Some(self.client.clone()))
+            .with_shared_topic(self.config.topic.clone())
+        )
+    }
+
+    async fn consumer(&self, options: ConsumerOptions) -> Result<Self::Consumer> {
+        let subscription = self.config.subscription
+            .as_deref()
+            .unwrap_or(&options.consumer_tag);
+
+        Ok(
+            PubSubConsumer::new(self.client.subscription(subscription))
+                .with_delivery_guarantee(options.delivery_guarantee)
+                .with_max_in_flight(options.max_in_flight)
+        )
+    }
+
+    async fn channels(&self) -> Result<Vec<String>> {
+        self.client
+            .get_topics(None)
+            .await
+            .map_err(|e| Error::Unknown(anyhow::anyhow!(e)))
+    }
+}
+
+
+pub struct PubSubBrokerBuilder {
+    project_id: Option<String>,
+    topic: Option<String>,
+    subscription: Option<String>,
+}
+
+impl PubSubBrokerBuilder {
+    pub fn new() -> Self {
+        Self {
+            project_id: None,
+            topic: None,
+            subscription: None,
+        }
+    }
+
+    pub fn with_project_id(mut self, project_id: impl Into<String>) -> Self {
+        self.project_id = Some(project_id.into());
+        self
+    }
+
+    /// Use a single topic for every channel.
+    pub fn with_topic(mut self, topic: impl Into<String>) -> Self {
+        self.topic = Some(topic.into());
+        self
+    }
+
+    /// Use a single subscription for every consumer.
+    pub fn with_subscription(mut self, subscription: impl Into<String>) -> Self {
+        self.subscription = Some(subscription.into());
+        self
+    }
+}
+
+impl Default for PubSubBrokerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl BrokerBuilder for PubSubBrokerBuilder {
+    type Broker = PubSubBroker;
+
+    async fn build(&self) -> Result<Self::Broker> {
+        let config = PubSubBrokerConfig {
+            project_id: self.project_id.clone(),
+            topic: self.topic.clone(),
+            subscription: self.subscription.clone(),
+        };
+        let client = config.into_client().await?;
+
+        Ok(PubSubBroker::new(config, client))
+    }
+}
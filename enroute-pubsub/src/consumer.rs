@@ -0,0 +1,149 @@
+use std::{sync::Arc, pin::Pin, collections::HashMap};
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use chrono::{DateTime, Utc};
+use gcloud_pubsub::{subscriber::ReceivedMessage, subscription::Subscription};
+
+use enroute_core::{
+    consumer::{Consumer, DeliveryGuarantee},
+    in_flight::limit_in_flight,
+    event::{Event, parse_extension_value},
+    envelope::{Acker, Envelope, NoOpAcker, DELIVERY_COUNT_EXTENSION},
+    error::{Error, Result},
+};
+
+use crate::acker::PubSubAcker;
+
+
+/// The attributes mapped to CloudEvents context attributes rather than extensions.
+const CONTEXT_ATTRIBUTES: &[&str] = &[
+    "ce-specversion",
+    "ce-id",
+    "ce-type",
+    "ce-source",
+    "ce-time",
+    "ce-subject",
+    "ce-dataschema",
+];
+
+fn get_attribute<'a>(message: &'a ReceivedMessage, key: &str) -> Result<&'a str> {
+    message.message.attributes
+        .get(key)
+        .map(String::as_str)
+        .ok_or_else(|| Error::Deserialization(format!("Missing {} attribute", key)))
+}
+
+fn message_to_event(message: &ReceivedMessage) -> Result<Event> {
+    let attributes = &message.message.attributes;
+    let event_type = get_attribute(message, "ce-type")?;
+
+    Event::builder()
+        .id(
+            attributes
+                .get("ce-id")
+                .unwrap_or(&message.message.message_id)
+        )
+        .type_(event_type)
+        .source(get_attribute(message, "ce-source")?)
+        .maybe_subject(attributes.get("ce-subject"))
+        .maybe_time(
+            attributes
+                .get("ce-time")
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.with_timezone(&Utc))
+        )
+        .maybe_schema_url(attributes.get("ce-dataschema").map(String::as_str))
+        .extensions(
+            attributes
+                .iter()
+                .filter(|(k, _)| !CONTEXT_ATTRIBUTES.contains(&k.as_str()))
+                .filter_map(|(k, v)| Some((k.strip_prefix("ce-")?, parse_extension_value(v))))
+                .collect::<HashMap<_, _>>()
+        )
+        .build_raw_with_content_type(
+            event_type,
+            message.message.data.clone(),
+            attributes
+                .get("content-type")
+                .map(String::as_str)
+                .unwrap_or("application/json"),
+        )
+}
+
+
+/// A Pub/Sub consumer that streaming pulls the events of a subscription.
+///
+/// On subscriptions with a dead-letter policy, the delivery attempt of each message is
+/// exposed as its delivery count.
+pub struct PubSubConsumer {
+    subscription: Subscription,
+    delivery_guarantee: Option<DeliveryGuarantee>,
+    max_in_flight: Option<usize>,
+}
+
+impl PubSubConsumer {
+    pub fn new(subscription: Subscription) -> Self {
+        Self {
+            subscription,
+            delivery_guarantee: None,
+            max_in_flight: None,
+        }
+    }
+
+    /// Set the delivery guarantee of the consumer.
+    /// 
+    /// With [`DeliveryGuarantee::AtMostOnce`] each message is acked before its envelope
+    /// is yielded. Otherwise it is only acked when the envelope is acked.
+    pub fn with_delivery_guarantee(mut self, guarantee: Option<DeliveryGuarantee>) -> Self {
+        self.delivery_guarantee = guarantee;
+        self
+    }
+
+    /// Set the maximum number of envelopes yielded but not yet acked or nacked.
+    pub fn with_max_in_flight(mut self, max: Option<usize>) -> Self {
+        self.max_in_flight = max;
+        self
+    }
+
+    /// Returns a reference to the underlying subscription.
+    pub fn subscription(&self) -> &Subscription {
+        &self.subscription
+    }
+}
+
+#[async_trait]
+impl Consumer for PubSubConsumer {
+    async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
+        let delivery_guarantee = self.delivery_guarantee;
+        let stream = self.subscription
+            .subscribe(None)
+            .await
+            .map_err(|e| Error::Consumer(e.to_string()))?
+            .then(move |message| async move {
+                let mut event = message_to_event(&message)?;
+
+                if let Some(attempt) = message.delivery_attempt() {
+                    event.0.set_extension(DELIVERY_COUNT_EXTENSION, attempt as i64);
+                }
+
+                let acker: Arc<dyn Acker> = match delivery_guarantee {
+                    Some(DeliveryGuarantee::AtMostOnce) => {
+                        message
+                            .ack()
+                            .await
+                            .map_err(|e| Error::Consumer(e.to_string()))?;
+
+                        Arc::new(NoOpAcker)
+                    }
+                    _ => Arc::new(PubSubAcker::new(message)),
+                };
+
+                Ok(Envelope::new(event, acker))
+            });
+
+        Ok(match self.max_in_flight {
+            Some(max) => limit_in_flight(Box::pin(stream), max),
+            None => Box::pin(stream),
+        })
+    }
+}
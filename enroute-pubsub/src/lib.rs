@@ -0,0 +1,14 @@
+#[allow(unused_extern_crates)]
+extern crate self as enroute_pubsub;
+
+pub mod publisher;
+pub mod consumer;
+pub mod broker;
+pub mod acker;
+
+pub use crate::{
+    broker::{PubSubBroker, PubSubBrokerBuilder, PubSubBrokerConfig},
+    consumer::PubSubConsumer,
+    publisher::PubSubPublisher,
+    acker::PubSubAcker,
+};
@@ -0,0 +1,170 @@
+use std::{collections::HashMap, future::Future};
+use async_trait::async_trait;
+use gcloud_googleapis::pubsub::v1::PubsubMessage;
+use gcloud_pubsub::{client::Client, publisher::Publisher as GcloudPublisher};
+
+use enroute_core::{
+    event::{Event, ExtensionValue, extension_value_to_string},
+    error::{Error, Result},
+    publisher::{Publisher, NativePublisher},
+};
+
+
+/// A Pub/Sub publisher that sends events in the CloudEvents binary content mode.
+///
+/// The event data is sent as the message data, while its attributes are sent as `ce-*`
+/// message attributes, including extensions, and its content type as `content-type`.
+#[derive(Clone)]
+pub struct PubSubPublisher {
+    publisher: GcloudPublisher,
+    topic: String,
+    default_extensions: HashMap<String, ExtensionValue>,
+    strict_channel_check: bool,
+    client: Option<Client>,
+    shared_topic: Option<String>,
+}
+
+impl PubSubPublisher {
+    pub fn new(publisher: GcloudPublisher, topic: String) -> Self {
+        Self {
+            publisher,
+            topic,
+            default_extensions: HashMap::new(),
+            strict_channel_check: false,
+            client: None,
+            shared_topic: None,
+        }
+    }
+
+    /// Set the client used by [`PubSubPublisher::publish_to`] to publish to other topics.
+    pub fn with_client(mut self, client: Option<Client>) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Set the topic used for every channel, as set by
+    /// [`crate::broker::PubSubBrokerConfig::topic`].
+    pub fn with_shared_topic(mut self, topic: Option<String>) -> Self {
+        self.shared_topic = topic;
+        self
+    }
+
+    /// Set whether [`Publisher::publish_typed`] rejects event data of another channel.
+    pub fn with_strict_channel_check(mut self, strict: bool) -> Self {
+        self.strict_channel_check = strict;
+        self
+    }
+
+    /// Set extensions applied to every published event that doesn't already carry them.
+    pub fn with_default_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.default_extensions = extensions;
+        self
+    }
+
+    /// Returns a reference to the underlying publisher.
+    pub fn publisher(&self) -> &GcloudPublisher {
+        &self.publisher
+    }
+
+    pub async fn publish(&self, event: Event) -> Result<()> {
+        let message = self.message(event)?;
+
+        Self::send(&self.publisher, message).await
+    }
+
+    /// Publish an event to the topic of another channel, or to the shared topic when one
+    /// is set.
+    ///
+    /// The event is published through a publisher created for the topic, which is shut
+    /// down once the message is sent.
+    ///
+    /// # Arguments
+    /// * `channel` - The channel naming the topic to publish to.
+    /// * `event` - The event to publish.
+    ///
+    /// # Returns
+    /// A result indicating success or failure. Fails with [`Error::Unsupported`] when the
+    /// publisher was created without a client.
+    pub async fn publish_to(&self, channel: &str, event: Event) -> Result<()> {
+        let client = self.client
+            .as_ref()
+            .ok_or_else(|| Error::Unsupported("publishing to another channel".to_string()))?;
+        let topic = self.shared_topic
+            .as_deref()
+            .unwrap_or(channel);
+        let message = self.message(event)?;
+
+        let mut publisher = client.topic(topic).new_publisher(None);
+        let result = Self::send(&publisher, message).await;
+        publisher.shutdown().await;
+
+        result
+    }
+
+    fn message(&self, mut event: Event) -> Result<PubsubMessage> {
+        event.apply_default_extensions(&self.default_extensions);
+
+        let mut attributes = event
+            .extensions()
+            .into_iter()
+            .map(|(k, v)| (format!("ce-{}", k), extension_value_to_string(&v)))
+            .collect::<HashMap<_, _>>();
+
+        attributes.extend(
+            [
+                ("ce-specversion", Some(event.specversion())),
+                ("ce-id", Some(event.id().to_string())),
+                ("ce-type", Some(event.type_().to_string())),
+                ("ce-source", Some(event.source().to_string())),
+                ("ce-time", event.time().map(|t| t.to_rfc3339())),
+                ("ce-subject", event.subject().map(str::to_string)),
+                ("ce-dataschema", event.dataschema().map(|url| url.as_str().to_string())),
+                ("content-type", event.datacontenttype().map(str::to_string)),
+            ]
+            .into_iter()
+            .filter_map(|(k, v)| Some((k.to_string(), v?)))
+        );
+
+        Ok(PubsubMessage {
+            data: event.data_as_bytes()?,
+            attributes,
+            ..Default::default()
+        })
+    }
+
+    async fn send(publisher: &GcloudPublisher, message: PubsubMessage) -> Result<()> {
+        publisher
+            .publish(message)
+            .await
+            .get()
+            .await
+            .map_err(|e| Error::Publisher(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Publisher for PubSubPublisher {
+    async fn publish_event(&self, event: Event) -> Result<()> {
+        self.publish(event).await
+    }
+
+    async fn publish_event_to(&self, channel: &str, event: Event) -> Result<()> {
+        self.publish_to(channel, event).await
+    }
+
+    fn channel(&self) -> &str {
+        &self.topic
+    }
+
+    fn strict_channel_check(&self) -> bool {
+        self.strict_channel_check
+    }
+}
+
+impl NativePublisher for PubSubPublisher {
+    fn publish(&self, event: Event) -> impl Future<Output = Result<()>> + Send {
+        PubSubPublisher::publish(self, event)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,67 @@
+use std::{sync::{Arc, atomic::{AtomicBool, Ordering}}, time::Duration};
+use async_trait::async_trait;
+use aws_sdk_sqs::Client;
+
+use enroute_core::{envelope::Acker, error::{Error, Result}};
+
+
+/// An acker that deletes an SQS message once it is acked.
+///
+/// Nacking leaves the message in the queue, so it is delivered again once its
+/// visibility timeout expires. Extending the deadline sets the visibility timeout of the
+/// message to the given duration from now.
+#[derive(Debug, Clone)]
+pub struct SqsAcker {
+    client: Client,
+    queue_url: String,
+    receipt_handle: String,
+    done: Arc<AtomicBool>,
+}
+
+impl SqsAcker {
+    pub(crate) fn new(client: Client, queue_url: String, receipt_handle: String) -> Self {
+        Self {
+            client,
+            queue_url,
+            receipt_handle,
+            done: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Returns the receipt handle of the message.
+    pub fn receipt_handle(&self) -> &str {
+        &self.receipt_handle
+    }
+}
+
+#[async_trait]
+impl Acker for SqsAcker {
+    async fn ack(&self) {
+        if self.done.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let _ = self.client
+            .delete_message()
+            .queue_url(&self.queue_url)
+            .receipt_handle(&self.receipt_handle)
+            .send()
+            .await;
+    }
+
+    async fn nack(&self) {
+        self.done.store(true, Ordering::SeqCst);
+    }
+
+    async fn extend_deadline(&self, by: Duration) -> Result<()> {
+        self.client
+            .change_message_visibility()
+            .queue_url(&self.queue_url)
+            .receipt_handle(&self.receipt_handle)
+            .visibility_timeout(i32::try_from(by.as_secs()).unwrap_or(i32::MAX))
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|e| Error::Consumer(e.to_string()))
+    }
+}
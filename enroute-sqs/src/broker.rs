@@ -0,0 +1,219 @@
+use std::{str::FromStr, collections::HashMap};
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_sqs::{Client, error::DisplayErrorContext};
+
+use enroute_core::{
+    publisher::PublisherOptions,
+    consumer::ConsumerOptions,
+    broker::{Broker, BrokerBuilder},
+    admin::UnsupportedAdmin,
+    error::{Error, Result},
+};
+
+use enroute_sqs::{
+    consumer::SqsConsumer,
+    publisher::SqsPublisher,
+};
+
+
+fn parse_extra<T: FromStr>(extra: &HashMap<String, String>, key: &str) -> Result<Option<T>> {
+    extra
+        .get(key)
+        .map(|value| value
+            .parse::<T>()
+            .map_err(|_| Error::Builder(format!("invalid {}: {}", key, value)))
+        )
+        .transpose()
+}
+
+
+/// Resolve the URL of the queue backing a channel: the queue URL shared by every channel
+/// if one is configured, else the channel itself if it is a queue URL, else the URL of
+/// the queue of the same name.
+pub(crate) async fn resolve_queue_url(client: &Client, shared_queue_url: Option<&str>, channel: &str) -> Result<String> {
+    if let Some(queue_url) = shared_queue_url {
+        return Ok(queue_url.to_string());
+    }
+
+    if channel.starts_with("https://") || channel.starts_with("http://") {
+        return Ok(channel.to_string());
+    }
+
+    client
+        .get_queue_url()
+        .queue_name(channel)
+        .send()
+        .await
+        .map_err(|e| Error::Unknown(anyhow::anyhow!(DisplayErrorContext(e).to_string())))?
+        .queue_url
+        .ok_or_else(|| Error::Unknown(anyhow::anyhow!("no queue URL for {}", channel)))
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqsBrokerConfig {
+    pub region: String,
+    /// The URL of the queue used for every channel. When not set, each channel is
+    /// resolved to the queue of the same name, or used as is if it is a queue URL.
+    #[serde(default)]
+    pub queue_url: Option<String>,
+    /// Overrides the SQS endpoint, e.g. to target a local emulator.
+    #[serde(default)]
+    pub endpoint_url: Option<String>,
+}
+
+impl SqsBrokerConfig {
+    /// Create an SQS client from the configuration, loading credentials from the
+    /// environment.
+    pub async fn into_client(&self) -> Client {
+        let mut loader = aws_config::defaults(BehaviorVersion::latest())
+            .region(Region::new(self.region.clone()));
+
+        if let Some(endpoint_url) = &self.endpoint_url {
+            loader = loader.endpoint_url(endpoint_url);
+        }
+
+        Client::new(&loader.load().await)
+    }
+}
+
+
+/// A broker publishing and consuming events through Amazon SQS queues.
+///
+/// Publishers and consumers accept the following extra settings:
+/// * `wait_time_seconds` - How long a receive request waits for messages (consumers).
+/// * `max_number_of_messages` - The maximum number of messages per receive request (consumers).
+/// * `visibility_timeout` - The visibility timeout of received messages (consumers).
+/// * `message_group_id` - The message group ID of events sent to FIFO queues (publishers).
+#[derive(Clone)]
+pub struct SqsBroker {
+    config: SqsBrokerConfig,
+    client: Client,
+}
+
+impl SqsBroker {
+    pub fn new(config: SqsBrokerConfig, client: Client) -> Self {
+        Self { config, client }
+    }
+
+    pub fn builder() -> SqsBrokerBuilder {
+        SqsBrokerBuilder::new()
+    }
+
+    /// Returns a reference to the underlying client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Resolve the URL of the queue backing a channel.
+    /// 
+    /// # Arguments
+    /// * `channel` - The channel to resolve, either a queue name or a queue URL.
+    /// 
+    /// # Returns
+    /// A result containing the queue URL or an error.
+    pub async fn queue_url(&self, channel: &str) -> Result<String> {
+        resolve_queue_url(&self.client, self.config.queue_url.as_deref(), channel).await
+    }
+}
+
+#[async_trait]
+impl Broker for SqsBroker {
+    type Publisher = SqsPublisher;
+    type Consumer = SqsConsumer;
+    type Admin = UnsupportedAdmin;
+
+    async fn publisher(&self, options: PublisherOptions) -> Result<Self::Publisher> {
+        Ok(
+            SqsPublisher::new(
+                self.client.clone(),
+                self.queue_url(&options.channel).await?,
+                options.channel.to_string(),
+            )
+            .with_default_extensions(options.default_extensions)
+            .with_strict_channel_check(options.strict_channel_check)
+            .with_message_group_id(options.extra.get("message_group_id").cloned())
+            .with_shared_queue_url(self.config.queue_url.clone())
+        )
+    }
+
+    async fn consumer(&self, options: ConsumerOptions) -> Result<Self::Consumer> {
+        let mut consumer = SqsConsumer::new(
+            self.client.clone(),
+            self.queue_url(&options.channel).await?,
+        )
+        .with_visibility_timeout(parse_extra(&options.extra, "visibility_timeout")?)
+        .with_delivery_guarantee(options.delivery_guarantee)
+        .with_max_in_flight(options.max_in_flight);
+
+        if let Some(seconds) = parse_extra(&options.extra, "wait_time_seconds")? {
+            consumer = consumer.with_wait_time_seconds(seconds);
+        }
+
+        if let Some(max) = parse_extra(&options.extra, "max_number_of_messages")? {
+            consumer = consumer.with_max_messages(max);
+        }
+
+        Ok(consumer)
+    }
+}
+
+
+pub struct SqsBrokerBuilder {
+    region: Option<String>,
+    queue_url: Option<String>,
+    endpoint_url: Option<String>,
+}
+
+impl SqsBrokerBuilder {
+    pub fn new() -> Self {
+        Self {
+            region: None,
+            queue_url: None,
+            endpoint_url: None,
+        }
+    }
+
+    pub fn with_region(mut self, region: impl Into<String>) -> Self {
+        self.region = Some(region.into());
+        self
+    }
+
+    /// Use a single queue for every channel.
+    pub fn with_queue_url(mut self, queue_url: impl Into<String>) -> Self {
+        self.queue_url = Some(queue_url.into());
+        self
+    }
+
+    /// Override the SQS endpoint, e.g. to target a local emulator.
+    pub fn with_endpoint_url(mut self, endpoint_url: impl Into<String>) -> Self {
+        self.endpoint_url = Some(endpoint_url.into());
+        self
+    }
+}
+
+impl Default for SqsBrokerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl BrokerBuilder for SqsBrokerBuilder {
+    type Broker = SqsBroker;
+
+    async fn build(&self) -> Result<Self::Broker> {
+        let config = SqsBrokerConfig {
+            region: self.region
+                .clone()
+                .ok_or_else(|| Error::Builder("missing region".to_string()))?,
+            queue_url: self.queue_url.clone(),
+            endpoint_url: self.endpoint_url.clone(),
+        };
+        let client = config.into_client().await;
+
+        Ok(SqsBroker::new(config, client))
+    }
+}
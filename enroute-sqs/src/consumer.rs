@@ -0,0 +1,203 @@
+use std::{sync::Arc, pin::Pin, time::Duration};
+use async_trait::async_trait;
+use async_stream::stream;
+use futures::Stream;
+use aws_sdk_sqs::{Client, error::DisplayErrorContext, types::MessageSystemAttributeName};
+
+use enroute_core::{
+    consumer::{Consumer, DeliveryGuarantee},
+    in_flight::limit_in_flight,
+    pause::PauseGate,
+    event::Event,
+    envelope::{Acker, Envelope, NoOpAcker, DELIVERY_COUNT_EXTENSION},
+    error::{Error, Result},
+};
+
+use crate::acker::SqsAcker;
+
+
+/// The default time a receive request waits for messages, in seconds.
+pub const DEFAULT_WAIT_TIME_SECONDS: i32 = 20;
+/// The default maximum number of messages fetched per receive request.
+pub const DEFAULT_MAX_MESSAGES: i32 = 10;
+/// The delay before retrying a failed receive request, doubled after each consecutive
+/// failure.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// The maximum delay before retrying a failed receive request.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// An SQS consumer that long polls a queue for structured CloudEvents JSON messages.
+///
+/// SQS queues have no consumer groups: all consumers of a queue compete for its
+/// messages, regardless of their consumer tag. The approximate receive count of each
+/// message is exposed as its delivery count.
+///
+/// A failed receive request is yielded as an error and retried after a delay, doubling
+/// from one second up to 30 seconds while requests keep failing.
+#[derive(Clone)]
+pub struct SqsConsumer {
+    client: Client,
+    queue_url: String,
+    wait_time_seconds: i32,
+    max_messages: i32,
+    visibility_timeout: Option<i32>,
+    delivery_guarantee: Option<DeliveryGuarantee>,
+    max_in_flight: Option<usize>,
+    pause: PauseGate,
+}
+
+impl SqsConsumer {
+    pub fn new(client: Client, queue_url: String) -> Self {
+        Self {
+            client,
+            queue_url,
+            wait_time_seconds: DEFAULT_WAIT_TIME_SECONDS,
+            max_messages: DEFAULT_MAX_MESSAGES,
+            visibility_timeout: None,
+            delivery_guarantee: None,
+            max_in_flight: None,
+            pause: PauseGate::new(),
+        }
+    }
+
+    /// Set how long a receive request waits for messages, in seconds (at most 20).
+    pub fn with_wait_time_seconds(mut self, seconds: i32) -> Self {
+        self.wait_time_seconds = seconds;
+        self
+    }
+
+    /// Set the maximum number of messages fetched per receive request (at most 10).
+    pub fn with_max_messages(mut self, max: i32) -> Self {
+        self.max_messages = max;
+        self
+    }
+
+    /// Set the visibility timeout of received messages, in seconds, overriding the
+    /// queue's default.
+    pub fn with_visibility_timeout(mut self, seconds: Option<i32>) -> Self {
+        self.visibility_timeout = seconds;
+        self
+    }
+
+    /// Set the delivery guarantee of the consumer.
+    /// 
+    /// With [`DeliveryGuarantee::AtMostOnce`] each message is deleted before its envelope
+    /// is yielded. Otherwise it is only deleted when the envelope is acked.
+    pub fn with_delivery_guarantee(mut self, guarantee: Option<DeliveryGuarantee>) -> Self {
+        self.delivery_guarantee = guarantee;
+        self
+    }
+
+    /// Set the maximum number of envelopes yielded but not yet acked or nacked.
+    pub fn with_max_in_flight(mut self, max: Option<usize>) -> Self {
+        self.max_in_flight = max;
+        self
+    }
+
+    /// Returns the URL of the queue the consumer polls.
+    pub fn queue_url(&self) -> &str {
+        &self.queue_url
+    }
+}
+
+#[async_trait]
+impl Consumer for SqsConsumer {
+    async fn stream_events(&self) -> Result<Pin<Box<dyn Stream<Item = Result<Envelope>> + Send>>> {
+        let consumer = self.clone();
+        let stream = stream! {
+            let mut retry_delay = RETRY_DELAY;
+
+            loop {
+                consumer.pause.wait().await;
+
+                let output = match consumer.client
+                    .receive_message()
+                    .queue_url(&consumer.queue_url)
+                    .wait_time_seconds(consumer.wait_time_seconds)
+                    .max_number_of_messages(consumer.max_messages)
+                    .set_visibility_timeout(consumer.visibility_timeout)
+                    .message_system_attribute_names(MessageSystemAttributeName::ApproximateReceiveCount)
+                    .send()
+                    .await
+                {
+                    Ok(output) => {
+                        retry_delay = RETRY_DELAY;
+                        output
+                    }
+                    Err(e) => {
+                        yield Err(Error::Consumer(DisplayErrorContext(e).to_string()));
+
+                        // Back off, so a persistent failure such as a missing queue doesn't
+                        // turn into a busy loop of requests.
+                        tokio::time::sleep(retry_delay).await;
+                        retry_delay = (retry_delay * 2).min(MAX_RETRY_DELAY);
+                        continue;
+                    }
+                };
+
+                for message in output.messages.unwrap_or_default() {
+                    let receive_count = message.attributes
+                        .as_ref()
+                        .and_then(|attributes| attributes.get(&MessageSystemAttributeName::ApproximateReceiveCount))
+                        .and_then(|count| count.parse::<i64>().ok());
+                    let (Some(body), Some(receipt_handle)) = (message.body, message.receipt_handle) else {
+                        continue;
+                    };
+
+                    let mut event = match serde_json::from_str::<Event>(&body) {
+                        Ok(event) => event,
+                        Err(e) => {
+                            yield Err(Error::Deserialization(e.to_string()));
+                            continue;
+                        }
+                    };
+
+                    if let Some(count) = receive_count {
+                        event.0.set_extension(DELIVERY_COUNT_EXTENSION, count);
+                    }
+
+                    let acker: Arc<dyn Acker> = match consumer.delivery_guarantee {
+                        Some(DeliveryGuarantee::AtMostOnce) => {
+                            if let Err(e) = consumer.client
+                                .delete_message()
+                                .queue_url(&consumer.queue_url)
+                                .receipt_handle(&receipt_handle)
+                                .send()
+                                .await
+                            {
+                                yield Err(Error::Consumer(DisplayErrorContext(e).to_string()));
+                                continue;
+                            }
+
+                            Arc::new(NoOpAcker)
+                        }
+                        _ => Arc::new(SqsAcker::new(
+                            consumer.client.clone(),
+                            consumer.queue_url.clone(),
+                            receipt_handle,
+                        )),
+                    };
+
+                    yield Ok(Envelope::new(event, acker));
+                }
+            }
+        };
+
+        Ok(match self.max_in_flight {
+            Some(max) => limit_in_flight(Box::pin(stream), max),
+            None => Box::pin(stream),
+        })
+    }
+
+    /// Pause polling the queue. Messages already received are still yielded, and
+    /// become visible to other consumers again once their visibility timeout expires.
+    async fn pause(&self) -> Result<()> {
+        self.pause.pause().await;
+        Ok(())
+    }
+
+    async fn resume(&self) -> Result<()> {
+        self.pause.resume().await;
+        Ok(())
+    }
+}
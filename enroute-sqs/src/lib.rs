@@ -0,0 +1,14 @@
+#[allow(unused_extern_crates)]
+extern crate self as enroute_sqs;
+
+pub mod publisher;
+pub mod consumer;
+pub mod broker;
+pub mod acker;
+
+pub use crate::{
+    broker::{SqsBroker, SqsBrokerBuilder, SqsBrokerConfig},
+    consumer::SqsConsumer,
+    publisher::SqsPublisher,
+    acker::SqsAcker,
+};
@@ -0,0 +1,182 @@
+use std::{collections::HashMap, future::Future};
+use async_trait::async_trait;
+use aws_sdk_sqs::{Client, error::DisplayErrorContext, types::MessageAttributeValue};
+
+use enroute_core::{
+    event::{Event, ExtensionValue, IDEMPOTENCY_KEY_EXTENSION},
+    error::{Error, Result},
+    publisher::{Publisher, NativePublisher},
+};
+
+use crate::broker::resolve_queue_url;
+
+
+fn string_attribute(value: impl Into<String>) -> Result<MessageAttributeValue> {
+    MessageAttributeValue::builder()
+        .data_type("String")
+        .string_value(value)
+        .build()
+        .map_err(|e| Error::Publisher(e.to_string()))
+}
+
+/// An SQS publisher that sends events as structured CloudEvents JSON message bodies.
+///
+/// The event's `ce-*` attributes are mirrored as message attributes, so they can be
+/// inspected without parsing the body. Extensions are only carried in the body, as
+/// SQS limits the number of attributes per message, except for the idempotency key.
+/// 
+/// The idempotency key of an event is mirrored as an `idempotencykey` attribute and,
+/// on FIFO queues, used as the message deduplication ID, falling back to the event ID.
+/// FIFO sends are grouped by the configured message group ID, or else by the event's
+/// subject, or the publisher's channel for events without one.
+#[derive(Clone)]
+pub struct SqsPublisher {
+    client: Client,
+    queue_url: String,
+    channel: String,
+    default_extensions: HashMap<String, ExtensionValue>,
+    strict_channel_check: bool,
+    message_group_id: Option<String>,
+    shared_queue_url: Option<String>,
+}
+
+impl SqsPublisher {
+    pub fn new(client: Client, queue_url: String, channel: String) -> Self {
+        Self {
+            client,
+            queue_url,
+            channel,
+            default_extensions: HashMap::new(),
+            strict_channel_check: false,
+            message_group_id: None,
+            shared_queue_url: None,
+        }
+    }
+
+    /// Set whether [`Publisher::publish_typed`] rejects event data of another channel.
+    pub fn with_strict_channel_check(mut self, strict: bool) -> Self {
+        self.strict_channel_check = strict;
+        self
+    }
+
+    /// Set extensions applied to every published event that doesn't already carry them.
+    pub fn with_default_extensions(mut self, extensions: HashMap<String, ExtensionValue>) -> Self {
+        self.default_extensions = extensions;
+        self
+    }
+
+    /// Set the message group ID of events sent to FIFO queues, instead of grouping them
+    /// by subject.
+    pub fn with_message_group_id(mut self, group_id: Option<String>) -> Self {
+        self.message_group_id = group_id;
+        self
+    }
+
+    /// Set the queue URL used for every channel, as
+    /// [`SqsBrokerConfig::queue_url`](crate::broker::SqsBrokerConfig::queue_url), so
+    /// [`SqsPublisher::publish_to`] resolves queues as the broker does.
+    pub fn with_shared_queue_url(mut self, queue_url: Option<String>) -> Self {
+        self.shared_queue_url = queue_url;
+        self
+    }
+
+    /// Returns the URL of the queue the publisher sends to.
+    pub fn queue_url(&self) -> &str {
+        &self.queue_url
+    }
+
+    /// Returns a reference to the underlying client.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub async fn publish(&self, event: Event) -> Result<()> {
+        self.publish_to(&self.queue_url, event).await
+    }
+
+    /// Publish an event to the given queue instead of the publisher's own queue.
+    /// 
+    /// # Arguments
+    /// * `queue` - The URL or name of the queue, resolved as
+    ///   [`SqsBroker::queue_url`](crate::broker::SqsBroker::queue_url) resolves channels.
+    /// * `event` - The event to be published.
+    /// 
+    /// # Returns
+    /// A result indicating success or failure.
+    pub async fn publish_to(&self, queue: &str, mut event: Event) -> Result<()> {
+        let queue_url = resolve_queue_url(&self.client, self.shared_queue_url.as_deref(), queue).await?;
+
+        event.apply_default_extensions(&self.default_extensions);
+
+        let body = serde_json::to_string(&event)
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        let attributes = [
+            ("ce-specversion", Some(event.specversion())),
+            ("ce-id", Some(event.id().to_string())),
+            ("ce-type", Some(event.type_().to_string())),
+            ("ce-source", Some(event.source().to_string())),
+            ("ce-time", event.time().map(|t| t.to_rfc3339())),
+            ("ce-subject", event.subject().map(str::to_string)),
+            ("ce-dataschema", event.dataschema().map(|url| url.as_str().to_string())),
+            ("ce-datacontenttype", event.datacontenttype().map(str::to_string)),
+            (IDEMPOTENCY_KEY_EXTENSION, event.idempotency_key()),
+        ];
+
+        let mut request = self.client
+            .send_message()
+            .queue_url(&queue_url)
+            .message_body(body);
+
+        if queue_url.ends_with(".fifo") {
+            let group_id = self.message_group_id
+                .clone()
+                .or_else(|| event.subject().map(str::to_string))
+                .unwrap_or_else(|| self.channel.clone());
+            let deduplication_id = event
+                .idempotency_key()
+                .unwrap_or_else(|| event.id().to_string());
+
+            request = request
+                .message_group_id(group_id)
+                .message_deduplication_id(deduplication_id);
+        }
+
+        for (key, value) in attributes {
+            if let Some(value) = value {
+                request = request.message_attributes(key, string_attribute(value)?);
+            }
+        }
+
+        request
+            .send()
+            .await
+            .map_err(|e| Error::Publisher(DisplayErrorContext(e).to_string()))?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Publisher for SqsPublisher {
+    async fn publish_event(&self, event: Event) -> Result<()> {
+        self.publish(event).await
+    }
+
+    async fn publish_event_to(&self, channel: &str, event: Event) -> Result<()> {
+        self.publish_to(channel, event).await
+    }
+
+    fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    fn strict_channel_check(&self) -> bool {
+        self.strict_channel_check
+    }
+}
+
+impl NativePublisher for SqsPublisher {
+    fn publish(&self, event: Event) -> impl Future<Output = Result<()>> + Send {
+        SqsPublisher::publish(self, event)
+    }
+}
\ No newline at end of file
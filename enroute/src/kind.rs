@@ -0,0 +1,69 @@
+use serde::{Serialize, Deserialize};
+
+use enroute_core::{
+    error::Result,
+    broker::{AnyBroker, IntoAnyBroker},
+};
+use enroute_memory::broker::{InMemoryBroker, InMemoryBrokerConfig};
+#[cfg(feature = "kafka")]
+use enroute_kafka::broker::{KafkaBroker, KafkaBrokerConfig};
+#[cfg(feature = "sqs")]
+use enroute_sqs::broker::{SqsBroker, SqsBrokerConfig};
+#[cfg(feature = "pubsub")]
+use enroute_pubsub::broker::{PubSubBroker, PubSubBrokerConfig};
+#[cfg(feature = "mqtt")]
+use enroute_mqtt::broker::{MqttBroker, MqttBrokerConfig};
+
+
+/// The configuration of a broker of any backend, to select the backend by
+/// configuration alone.
+///
+/// The backend is named by the `backend` field, next to the fields of its config:
+///
+/// ```yaml
+/// backend: kafka
+/// bootstrap_servers:
+///   - localhost:9092
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum BrokerKind {
+    InMemory(InMemoryBrokerConfig),
+    #[cfg(feature = "kafka")]
+    Kafka(KafkaBrokerConfig),
+    #[cfg(feature = "sqs")]
+    Sqs(SqsBrokerConfig),
+    #[cfg(feature = "pubsub")]
+    #[serde(rename = "pubsub")]
+    PubSub(PubSubBrokerConfig),
+    #[cfg(feature = "mqtt")]
+    Mqtt(MqttBrokerConfig),
+}
+
+impl BrokerKind {
+    /// Build the broker of the configured backend.
+    ///
+    /// # Returns
+    /// A result containing the type-erased broker or an error.
+    pub async fn build(self) -> Result<AnyBroker> {
+        Ok(match self {
+            BrokerKind::InMemory(config) => InMemoryBroker::new(config).into_any(),
+            #[cfg(feature = "kafka")]
+            BrokerKind::Kafka(config) => KafkaBroker::new(config).into_any(),
+            #[cfg(feature = "sqs")]
+            BrokerKind::Sqs(config) => {
+                let client = config.into_client().await;
+
+                SqsBroker::new(config, client).into_any()
+            }
+            #[cfg(feature = "pubsub")]
+            BrokerKind::PubSub(config) => {
+                let client = config.into_client().await?;
+
+                PubSubBroker::new(config, client).into_any()
+            }
+            #[cfg(feature = "mqtt")]
+            BrokerKind::Mqtt(config) => MqttBroker::new(config)?.into_any(),
+        })
+    }
+}
@@ -1,27 +1,104 @@
+#[cfg(feature = "broker")]
+mod kind;
+
+pub use enroute_core::{
+    error::{Error, ErrorKind, Result},
+    event::{EventData, EventBuilder, Event, SchemaResolver, SerdeOptions, IDEMPOTENCY_KEY_EXTENSION, CORRELATION_ID_EXTENSION, CAUSATION_ID_EXTENSION, REDACTED_VALUE, MERGE_PATCH_CONTENT_TYPE, EXPIRY_TIME_EXTENSION},
+    clock::{Clock, SystemClock, FixedClock},
+    codec::{Codec, CBOR_CONTENT_TYPE},
+    registry::EventRegistry,
+    versioning::{VersionedDeserializer, DATA_SCHEMA_VERSION_EXTENSION},
+};
+#[cfg(feature = "cbor")]
+pub use enroute_core::codec::CborCodec;
+#[cfg(feature = "signing")]
+pub use enroute_core::signing::{SigningPublisher, VerifyingConsumer, SigningKey, VerifyingKey, InvalidSignaturePolicy, SIGNATURE_EXTENSION, canonical_bytes};
+#[cfg(feature = "broker")]
 pub use enroute_core::{
-    error::{Error, Result},
-    event::{EventData, EventBuilder, Event},
-    envelope::{Envelope, Acker},
+    envelope::{Envelope, Acker, AckGroup, TOMBSTONE_EXTENSION},
     broker::{Broker, AnyBroker, IntoAnyBroker, BrokerBuilder},
-    publisher::{Publisher, AnyPublisher, IntoAnyPublisher, PublisherOptions},
-    consumer::{Consumer, AnyConsumer, IntoAnyConsumer, ConsumerOptions},
+    admin::{Admin, AnyAdmin, UnsupportedAdmin},
+    publisher::{Publisher, AnyPublisher, IntoAnyPublisher, NativePublisher, PublisherOptions},
+    consumer::{Consumer, AnyConsumer, IntoAnyConsumer, ConsumerOptions, ConsumerEvent, Partition, DeliveryGuarantee},
+    sampling::{SamplingPublisher, SampleRate},
+    window::{WindowedConsumer, WindowPolicy, Window},
+    reorder::{ReorderingConsumer, LatePolicy},
+    rate_limit::RateLimitedConsumer,
+    catching::CatchingConsumer,
+    latency::{LatencyTrackingConsumer, LatencySummary},
+    outbox::{OutboxPublisher, OutboxStore, OutboxEntry, OutboxRelay, InMemoryOutboxStore},
+    splitter::SplitterConsumer,
+    ttl::TtlConsumer,
+    rpc::{RpcClient, RpcServer, REPLY_TO_EXTENSION},
+    failover::{FailoverPublisher, FailoverBroker},
+    pause::PauseGate,
+    replay::replay,
+    in_flight::limit_in_flight,
+    typed::{DeserializationFailure, TypedStream, FailureStream, TypedPublisher},
 };
 pub use enroute_macros::EventData;
+#[cfg(feature = "broker")]
+pub use enroute_macros::dispatch_events;
+#[cfg(feature = "broker")]
+pub use crate::kind::BrokerKind;
 
+#[cfg(feature = "broker")]
 pub mod memory {
     pub use enroute_memory::{
-        broker::{InMemoryBroker, InMemoryBrokerBuilder, InMemoryBrokerConfig},
-        publisher::InMemoryPublisher,
-        consumer::InMemoryConsumer,
+        broker::{InMemoryBroker, InMemoryBrokerBuilder, InMemoryBrokerConfig, ConsumerAssignment, BrokerStats, ChannelStats},
+        publisher::{InMemoryPublisher, SEQUENCE_EXTENSION},
+        consumer::{InMemoryConsumer, Subscription},
         acker::InMemoryAcker,
+        admin::InMemoryAdmin,
     };
 }
 
 #[cfg(feature = "kafka")]
 pub mod kafka {
     pub use enroute_kafka::{
-        broker::{KafkaBroker, KafkaBrokerBuilder, KafkaBrokerConfig},
-        publisher::KafkaPublisher,
-        consumer::KafkaConsumer,
+        broker::{KafkaBroker, KafkaBrokerBuilder, KafkaBrokerConfig, OffsetReset},
+        publisher::{KafkaPublisher, PublishOutcome, TimestampSource, DEFAULT_HEADER_PREFIX},
+        consumer::{KafkaConsumer, ConsumerLag, PartitionLag, TOMBSTONE_EVENT_TYPE},
+        transaction::{TransactionalPublisher, Transaction},
+        acker::KafkaAcker,
+        admin::KafkaAdmin,
+        context::KafkaConsumerContext,
+        reconnect::ReconnectingConsumer,
+    };
+}
+
+#[cfg(feature = "sqs")]
+pub mod sqs {
+    pub use enroute_sqs::{
+        broker::{SqsBroker, SqsBrokerBuilder, SqsBrokerConfig},
+        publisher::SqsPublisher,
+        consumer::SqsConsumer,
+        acker::SqsAcker,
+    };
+}
+
+#[cfg(feature = "pubsub")]
+pub mod pubsub {
+    pub use enroute_pubsub::{
+        broker::{PubSubBroker, PubSubBrokerBuilder, PubSubBrokerConfig},
+        publisher::PubSubPublisher,
+        consumer::PubSubConsumer,
+        acker::PubSubAcker,
     };
+}
+
+#[cfg(feature = "mqtt")]
+pub mod mqtt {
+    pub use enroute_mqtt::{
+        broker::{MqttBroker, MqttBrokerBuilder, MqttBrokerConfig},
+        client::MqttVersion,
+        publisher::MqttPublisher,
+        consumer::MqttConsumer,
+        acker::MqttAcker,
+    };
+}
+
+#[cfg(feature = "sse")]
+pub mod sse {
+    pub use enroute_core::sse::{format_sse, sse_stream, stream_sse};
 }
\ No newline at end of file